@@ -10,16 +10,15 @@ use crossterm::{execute, queue};
 use terminput::{Encoding, Event, KeyCode, KittyFlags, UnsupportedEvent, parse_event};
 
 fn print_events(encoding: Encoding) -> io::Result<()> {
-    let mut buf = [0; 16];
     loop {
         let event: Result<terminput::Event, UnsupportedEvent> = read()?.try_into();
 
         if let Ok(event) = event {
             println!("Event:   {:?}\r", event);
-            let written = event.encode(&mut buf, encoding);
-            if let Ok(written) = written {
-                println!("Encoded: {:?}\r", &buf[..written]);
-                if let Ok(Some(decoded)) = parse_event(&buf[..written]) {
+            let encoded = event.encode_to_vec(encoding);
+            if let Ok(encoded) = encoded {
+                println!("Encoded: {:?}\r", encoded);
+                if let Ok(Some(decoded)) = parse_event(&encoded) {
                     println!("Decoded: {:?}\r", decoded);
                 }
             }
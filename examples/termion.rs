@@ -6,17 +6,16 @@ use termion::raw::IntoRawMode;
 
 fn print_events() -> io::Result<()> {
     let stdin = io::stdin();
-    let mut buf = [0; 16];
 
     for event in stdin.events() {
         let event: Result<Event, UnsupportedEvent> = event?.try_into();
 
         if let Ok(event) = event {
             println!("Event:   {:?}\r", event);
-            let written = event.encode(&mut buf, Encoding::Xterm);
-            if let Ok(written) = written {
-                println!("Encoded: {:?}\r", &buf[..written]);
-                if let Ok(Some(decoded)) = parse_event(&buf[..written]) {
+            let encoded = event.encode_to_vec(Encoding::Xterm);
+            if let Ok(encoded) = encoded {
+                println!("Encoded: {:?}\r", encoded);
+                if let Ok(Some(decoded)) = parse_event(&encoded) {
                     println!("Decoded: {:?}\r", decoded);
                 }
             }
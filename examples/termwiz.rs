@@ -19,7 +19,6 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 fn print_events(mut terminal: BufferedTerminal<SystemTerminal>) {
-    let mut buf = [0; 16];
     loop {
         if let Ok(Some(event)) = terminal
             .terminal()
@@ -29,12 +28,11 @@ fn print_events(mut terminal: BufferedTerminal<SystemTerminal>) {
 
             if let Ok(event) = event {
                 println!("Event:   {:?}\r", event);
-                // Note: termwiz enables xterm's modifyOtherKeys setting which isn't supported by
-                // the encoder
-                let written = event.encode(&mut buf, Encoding::Xterm);
-                if let Ok(written) = written {
-                    println!("Encoded: {:?}\r", &buf[..written]);
-                    if let Ok(Some(decoded)) = parse_event(&buf[..written]) {
+                // termwiz enables level 2 of xterm's modifyOtherKeys setting
+                let encoded = event.encode_to_vec(Encoding::ModifyOtherKeys(2));
+                if let Ok(encoded) = encoded {
+                    println!("Encoded: {:?}\r", encoded);
+                    if let Ok(Some(decoded)) = parse_event(&encoded) {
                         println!("Decoded: {:?}\r", decoded);
                     }
                 }
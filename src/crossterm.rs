@@ -16,6 +16,7 @@ impl TryFrom<crossterm::event::Event> for Event {
             crossterm::event::Event::Resize(cols, rows) => Self::Resize {
                 cols: cols as u32,
                 rows: rows as u32,
+                pixel_size: None,
             },
         })
     }
@@ -31,12 +32,15 @@ impl TryFrom<Event> for crossterm::event::Event {
             Event::Key(key_event) => Self::Key(key_event.try_into()?),
             Event::Mouse(mouse_event) => Self::Mouse(mouse_event.try_into()?),
             Event::Paste(value) => Self::Paste(value),
-            Event::Resize { cols, rows } => Self::Resize(
+            Event::Resize { cols, rows, .. } => Self::Resize(
                 cols.try_into()
                     .map_err(|e| UnsupportedEvent(format!("{e:?}")))?,
                 rows.try_into()
                     .map_err(|e| UnsupportedEvent(format!("{e:?}")))?,
             ),
+            Event::Response(_) | Event::Unknown { .. } => {
+                Err(UnsupportedEvent(format!("{value:?}")))?
+            }
         })
     }
 }
@@ -50,6 +54,7 @@ impl TryFrom<crossterm::event::MouseEvent> for MouseEvent {
             column: value.column,
             row: value.row,
             modifiers: value.modifiers.try_into()?,
+            pixel_position: None,
         })
     }
 }
@@ -121,7 +126,10 @@ impl TryFrom<MouseButton> for crossterm::event::MouseButton {
             MouseButton::Left => Self::Left,
             MouseButton::Right => Self::Right,
             MouseButton::Middle => Self::Middle,
-            val @ MouseButton::Unknown => Err(UnsupportedEvent(format!("{val:?}")))?,
+            val @ (MouseButton::Unknown
+            | MouseButton::Back
+            | MouseButton::Forward
+            | MouseButton::Other(_)) => Err(UnsupportedEvent(format!("{val:?}")))?,
         })
     }
 }
@@ -130,12 +138,10 @@ impl TryFrom<crossterm::event::KeyEvent> for KeyEvent {
     type Error = UnsupportedEvent;
 
     fn try_from(value: crossterm::event::KeyEvent) -> Result<Self, Self::Error> {
-        Ok(Self {
-            code: value.code.try_into()?,
-            modifiers: value.modifiers.try_into()?,
-            kind: value.kind.try_into()?,
-            state: value.state.try_into()?,
-        })
+        Ok(Self::new(value.code.try_into()?)
+            .modifiers(value.modifiers.try_into()?)
+            .kind(value.kind.try_into()?)
+            .state(value.state.try_into()?))
     }
 }
 
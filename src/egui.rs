@@ -1,6 +1,6 @@
 use crate::{
-    Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers, MouseButton, MouseEvent,
-    MouseEventKind, UnsupportedEvent,
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    PixelPosition, UnsupportedEvent,
 };
 
 impl TryFrom<egui::Event> for Event {
@@ -15,23 +15,23 @@ impl TryFrom<egui::Event> for Event {
                 pressed,
                 repeat,
                 modifiers,
-            } => Ok(Self::Key(KeyEvent {
-                code: key.try_into()?,
-                modifiers: modifiers.try_into()?,
-                kind: if repeat {
-                    KeyEventKind::Repeat
-                } else if pressed {
-                    KeyEventKind::Press
-                } else {
-                    KeyEventKind::Release
-                },
-                state: KeyEventState::empty(),
-            })),
+            } => Ok(Self::Key(
+                KeyEvent::new(key.try_into()?)
+                    .modifiers(modifiers.try_into()?)
+                    .kind(if repeat {
+                        KeyEventKind::Repeat
+                    } else if pressed {
+                        KeyEventKind::Press
+                    } else {
+                        KeyEventKind::Release
+                    }),
+            )),
             egui::Event::PointerMoved(pos) => Ok(Self::Mouse(MouseEvent {
                 kind: MouseEventKind::Moved,
                 column: pos.x as u16,
                 row: pos.y as u16,
                 modifiers: KeyModifiers::empty(),
+                pixel_position: Some(pos.into()),
             })),
 
             egui::Event::PointerButton {
@@ -48,6 +48,7 @@ impl TryFrom<egui::Event> for Event {
                 column: pos.x as u16,
                 row: pos.y as u16,
                 modifiers: modifiers.try_into()?,
+                pixel_position: Some(pos.into()),
             })),
             egui::Event::WindowFocused(true) => Ok(Self::FocusGained),
             egui::Event::WindowFocused(false) => Ok(Self::FocusLost),
@@ -195,6 +196,15 @@ impl TryFrom<egui::Modifiers> for KeyModifiers {
     }
 }
 
+impl From<egui::Pos2> for PixelPosition {
+    fn from(value: egui::Pos2) -> Self {
+        Self {
+            x: value.x.round() as u16,
+            y: value.y.round() as u16,
+        }
+    }
+}
+
 impl TryFrom<egui::PointerButton> for MouseButton {
     type Error = UnsupportedEvent;
 
@@ -203,8 +213,8 @@ impl TryFrom<egui::PointerButton> for MouseButton {
             egui::PointerButton::Primary => Self::Left,
             egui::PointerButton::Secondary => Self::Right,
             egui::PointerButton::Middle => Self::Middle,
-            egui::PointerButton::Extra1 => Self::Unknown,
-            egui::PointerButton::Extra2 => Self::Unknown,
+            egui::PointerButton::Extra1 => Self::Back,
+            egui::PointerButton::Extra2 => Self::Forward,
         })
     }
 }
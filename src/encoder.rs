@@ -1,51 +1,371 @@
+use std::borrow::Cow;
+use std::fmt;
 use std::io::{self, Cursor, Seek, Write};
 
 use bitflags::bitflags;
 
 use crate::{
     Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers, MediaKeyCode,
-    ModifierDirection, ModifierKeyCode, MouseButton, MouseEvent, MouseEventKind,
+    ModifierDirection, ModifierKeyCode, MouseButton, MouseEncoding, MouseEvent, MouseEventKind,
+    PixelSize, TerminalResponse,
 };
 
 bitflags! {
     /// Controls which keyboard enhancement flags will be considered during encoding.
     /// These flags are described in Kitty's documentation on [progressive enhancement](https://sw.kovidgoyal.net/kitty/keyboard-protocol/#progressive-enhancement).
-    #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+    ///
+    /// The bit values match the ones used by the protocol, so they can be converted to and from
+    /// the integer that's sent to the terminal. See [`KittyFlagsCommand`] for sending the flags to
+    /// the terminal.
+    #[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Clone, Copy, Hash)]
     pub struct KittyFlags: u8 {
         /// Represent escape and modified keys using CSI-u sequences.
-        const DISAMBIGUATE_ESCAPE_CODES = 1<<1;
+        const DISAMBIGUATE_ESCAPE_CODES = 1;
         /// Report release and repeat events.
-        const REPORT_EVENT_TYPES = 1<<2;
+        const REPORT_EVENT_TYPES = 1<<1;
         /// Send [alternate keycodes](https://sw.kovidgoyal.net/kitty/keyboard-protocol/#key-codes)
-        /// in addition to the base keycode. The encoder currently only supports this for
-        /// alphabetic keys since applying this to other types of keys requires knowledge of the
-        /// keyboard layout used to type the key.
-        const REPORT_ALTERNATE_KEYS = 1<<3;
+        /// in addition to the base keycode. If the [shifted key](KeyEvent::shifted_key) isn't
+        /// supplied, the encoder can only infer it for alphabetic keys since applying this to
+        /// other types of keys requires knowledge of the keyboard layout used to type the key.
+        const REPORT_ALTERNATE_KEYS = 1<<2;
         /// Represent all keyboard events as CSI-u sequences.
-        const REPORT_ALL_KEYS_AS_ESCAPE_CODES = 1<<4;
+        const REPORT_ALL_KEYS_AS_ESCAPE_CODES = 1<<3;
+        /// Send the [text](KeyEvent::text) generated by the key as part of the CSI-u sequence.
+        /// This is only applicable when combined with
+        /// [`REPORT_ALL_KEYS_AS_ESCAPE_CODES`](KittyFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES).
+        const REPORT_ASSOCIATED_TEXT = 1<<4;
 
     }
 }
 
+impl From<KittyFlags> for u8 {
+    fn from(value: KittyFlags) -> Self {
+        value.bits()
+    }
+}
+
+impl From<u8> for KittyFlags {
+    /// Converts the integer used by the protocol into [`KittyFlags`]. Any unknown bits are
+    /// ignored.
+    fn from(value: u8) -> Self {
+        Self::from_bits_truncate(value)
+    }
+}
+
+/// Controls how the flags in [`KittyFlagsCommand::Set`] are applied to the current flags.
+#[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum KittyFlagsMode {
+    /// Replace the current flags with the given flags.
+    Replace = 1,
+    /// Enable the given flags and leave the rest unchanged.
+    Enable = 2,
+    /// Disable the given flags and leave the rest unchanged.
+    Disable = 3,
+}
+
+/// Sequences used to enable, disable, or query the
+/// [Kitty keyboard protocol](https://sw.kovidgoyal.net/kitty/keyboard-protocol/#progressive-enhancement).
+///
+/// These can be written to the terminal using [`KittyFlagsCommand::encode`] or by formatting the
+/// command with [`Display`](fmt::Display).
+///
+/// ```
+/// use terminput::{KittyFlags, KittyFlagsCommand};
+///
+/// let push = KittyFlagsCommand::Push(
+///     KittyFlags::DISAMBIGUATE_ESCAPE_CODES | KittyFlags::REPORT_EVENT_TYPES,
+/// );
+/// assert_eq!(push.to_string(), "\x1B[>3u");
+/// assert_eq!(KittyFlagsCommand::Pop(1).to_string(), "\x1B[<u");
+/// ```
+#[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum KittyFlagsCommand {
+    /// Push the flags onto the terminal's stack, making them the active flags (`CSI > flags u`).
+    Push(KittyFlags),
+    /// Pop the given number of entries off of the terminal's stack, restoring the flags that
+    /// were active previously (`CSI < number u`).
+    Pop(u16),
+    /// Modify the currently active flags (`CSI = flags ; mode u`).
+    Set(KittyFlags, KittyFlagsMode),
+    /// Request the currently active flags (`CSI ? u`). The terminal will reply with
+    /// [`TerminalResponse::KittyKeyboardFlags`].
+    Query,
+}
+
+impl KittyFlagsCommand {
+    /// Encode the command into the given buffer.
+    pub fn encode(&self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut buf = Cursor::new(buf);
+        write!(buf, "{self}")?;
+        Ok(buf.position() as usize)
+    }
+}
+
+impl fmt::Display for KittyFlagsCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Push(flags) => write!(f, "\x1B[>{}u", flags.bits()),
+            Self::Pop(1) => write!(f, "\x1B[<u"),
+            Self::Pop(count) => write!(f, "\x1B[<{count}u"),
+            Self::Set(flags, mode) => write!(f, "\x1B[={};{}u", flags.bits(), *mode as u8),
+            Self::Query => write!(f, "\x1B[?u"),
+        }
+    }
+}
+
 /// Encoding protocol used to control the output of [`Event::encode`]
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
 pub enum Encoding {
-    /// Encode using the legacy Xterm protocol.
+    /// Encode using the legacy Xterm protocol. This is the same as
+    /// [`Encoding::XtermWith`] using the default [`XtermOptions`].
     Xterm,
+    /// Encode using the legacy Xterm protocol with the given [`XtermOptions`].
+    XtermWith(XtermOptions),
+    /// Encode using Xterm's
+    /// [modifyOtherKeys](https://invisible-island.net/xterm/modified-keys.html) setting with the
+    /// given level. Modified keys that can't be represented by the legacy protocol are sent as
+    /// `CSI 27 ; modifiers ; codepoint ~`.
+    ///
+    /// - Level `0` is the same as [`Encoding::Xterm`].
+    /// - Level `1` only uses the extended encoding for keys that don't have a well-known legacy
+    ///   encoding, such as `Ctrl+Shift+a` or `Ctrl+1`.
+    /// - Level `2` or higher uses the extended encoding for all modified keys, such as `Ctrl+a`.
+    ModifyOtherKeys(u8),
     /// Encode using the Kitty protocol.
     Kitty(KittyFlags),
 }
 
+/// Terminal modes that affect the output of the legacy Xterm encoding.
+///
+/// Applications enable these modes by writing the corresponding sequence to the terminal.
+/// [`ModeEncoder`](crate::ModeEncoder) can be used to keep track of them automatically.
+///
+/// ```
+/// use terminput::{Encoding, Event, KeyCode, KeyEvent, XtermOptions};
+///
+/// let options = XtermOptions::new().application_cursor_keys(true);
+/// let up = Event::Key(KeyEvent::new(KeyCode::Up));
+/// assert_eq!(
+///     up.encode_to_vec(Encoding::XtermWith(options)).unwrap(),
+///     b"\x1BOA"
+/// );
+/// ```
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct XtermOptions {
+    /// Application cursor keys mode (DECCKM), enabled by `CSI ? 1 h`. Unmodified arrow, Home,
+    /// and End keys are sent as `SS3` sequences (`ESC O A`) instead of `CSI` sequences
+    /// (`ESC [ A`).
+    pub application_cursor_keys: bool,
+    /// Application keypad mode (DECKPAM), enabled by `ESC =`. Unmodified keys with the
+    /// [`KEYPAD`](KeyEventState::KEYPAD) state are sent as `SS3` sequences (`ESC O p` through
+    /// `ESC O y` for the digits) instead of the characters they represent.
+    pub application_keypad: bool,
+    /// The encoding used for mouse events.
+    pub mouse_encoding: MouseEncoding,
+}
+
+impl XtermOptions {
+    /// Creates a new [`XtermOptions`] with all modes disabled.
+    pub const fn new() -> Self {
+        Self {
+            application_cursor_keys: false,
+            application_keypad: false,
+            mouse_encoding: MouseEncoding::Sgr,
+        }
+    }
+
+    /// Sets whether application cursor keys mode (DECCKM) is enabled.
+    pub const fn application_cursor_keys(mut self, application_cursor_keys: bool) -> Self {
+        self.application_cursor_keys = application_cursor_keys;
+        self
+    }
+
+    /// Sets whether application keypad mode (DECKPAM) is enabled.
+    pub const fn application_keypad(mut self, application_keypad: bool) -> Self {
+        self.application_keypad = application_keypad;
+        self
+    }
+
+    /// Sets the encoding used for mouse events.
+    pub const fn mouse_encoding(mut self, mouse_encoding: MouseEncoding) -> Self {
+        self.mouse_encoding = mouse_encoding;
+        self
+    }
+}
+
+/// Controls how pasted text is sanitized before it's encoded.
+///
+/// Bracketed pastes end with `ESC [ 2 0 1 ~`. If the pasted text contains this sequence, the
+/// application treats everything after it as typed input, so pasting untrusted text could run
+/// arbitrary commands. Sanitizing the text prevents the paste from ending early.
+///
+/// ```
+/// use terminput::PasteSanitization;
+///
+/// let text = "echo hi\x1B[201~rm -rf ~\n";
+/// assert_eq!(
+///     PasteSanitization::StripTerminators.sanitize(text),
+///     "echo hirm -rf ~\n"
+/// );
+/// assert_eq!(
+///     PasteSanitization::StripControls.sanitize(text),
+///     "echo hi[201~rm -rf ~\n"
+/// );
+/// assert_eq!(
+///     PasteSanitization::EscapeControls.sanitize(text),
+///     "echo hi^[[201~rm -rf ~\n"
+/// );
+/// ```
+#[derive(Debug, Default, PartialOrd, Ord, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum PasteSanitization {
+    /// Send the text unchanged.
+    #[default]
+    Unchanged,
+    /// Remove any paste terminators (`ESC [ 2 0 1 ~`, or `CSI 2 0 1 ~` using the C1 control)
+    /// contained in the text.
+    StripTerminators,
+    /// Remove all control characters except for tab, line feed, and carriage return.
+    StripControls,
+    /// Replace all control characters except for tab, line feed, and carriage return with their
+    /// caret notation, such as `^[` for escape. C1 control characters are prefixed with `M-`,
+    /// the same as `cat -v`.
+    EscapeControls,
+}
+
+impl PasteSanitization {
+    /// Sanitizes the text. The text is only copied if it needs to be changed.
+    pub fn sanitize(self, text: &str) -> Cow<'_, str> {
+        match self {
+            Self::Unchanged => Cow::Borrowed(text),
+            Self::StripTerminators => {
+                let mut text = Cow::Borrowed(text);
+                // Removing a terminator could join the text around it into a new one
+                while text.contains("\x1B[201~") || text.contains("\u{9b}201~") {
+                    text = Cow::Owned(text.replace("\x1B[201~", "").replace("\u{9b}201~", ""));
+                }
+                text
+            }
+            Self::StripControls if text.chars().any(is_unsafe_paste_control) => Cow::Owned(
+                text.chars()
+                    .filter(|c| !is_unsafe_paste_control(*c))
+                    .collect(),
+            ),
+            Self::EscapeControls if text.chars().any(is_unsafe_paste_control) => {
+                let mut escaped = String::with_capacity(text.len() * 2);
+                for c in text.chars() {
+                    if !is_unsafe_paste_control(c) {
+                        escaped.push(c);
+                        continue;
+                    }
+                    let mut byte = c as u8;
+                    if byte >= 0x80 {
+                        escaped.push_str("M-");
+                        byte -= 0x80;
+                    }
+                    escaped.push('^');
+                    escaped.push((byte ^ 0x40) as char);
+                }
+                Cow::Owned(escaped)
+            }
+            Self::StripControls | Self::EscapeControls => Cow::Borrowed(text),
+        }
+    }
+}
+
+fn is_unsafe_paste_control(c: char) -> bool {
+    c.is_control() && !matches!(c, '\t' | '\n' | '\r')
+}
+
+// Longest possible Xterm key sequence, such as `ESC [ 1 ; 8 A` or an Alt-modified character.
+const MAX_XTERM_KEY_LEN: usize = 16;
+// Longest possible Kitty key sequence. This includes the CSI (2), three codepoints with
+// separators (7 + 8 + 8), modifiers and event type (4 + 2), associated text with each ASCII
+// character written as a separate codepoint (1 + 4 * 31), and the trailing character (1).
+pub(crate) const MAX_KITTY_KEY_LEN: usize = 157;
+// Longest possible mouse sequence, which uses the SGR encoding: `ESC [ < Cb ; Cx ; Cy M`
+const MAX_MOUSE_LEN: usize = 19;
+// Events that fit in this size are encoded on the stack when writing to an `io::Write`
+const STACK_BUF_LEN: usize = 128;
+
 impl Event {
     /// Encode the event into the given buffer using the supplied [`Encoding`] mode.
+    ///
+    /// Returns an error if the buffer is too small to hold the encoded event. A buffer with a
+    /// length of [`Event::max_encoded_len`] is always large enough.
+    ///
+    /// Pasted text is encoded unchanged. Use a [`ModeEncoder`](crate::ModeEncoder) with a
+    /// [`PasteSanitization`] to forward pastes from an untrusted clipboard.
     pub fn encode(&self, buf: &mut [u8], encoding: Encoding) -> io::Result<usize> {
         match encoding {
-            Encoding::Xterm => self.to_escape_sequence(buf),
-            Encoding::Kitty(flags) => self.to_kitty_escape_sequence(buf, flags),
+            Encoding::Xterm => self.to_escape_sequence(buf, XtermOptions::default()),
+            Encoding::XtermWith(options) => self.to_escape_sequence(buf, options),
+            Encoding::ModifyOtherKeys(level) => {
+                self.to_modify_other_keys_escape_sequence(buf, level, XtermOptions::default())
+            }
+            Encoding::Kitty(flags) => {
+                self.to_kitty_escape_sequence(buf, flags, XtermOptions::default())
+            }
         }
     }
 
-    fn to_escape_sequence(&self, buf: &mut [u8]) -> io::Result<usize> {
+    /// Encode the event into the given writer using the supplied [`Encoding`] mode. Returns the
+    /// number of bytes written.
+    ///
+    /// Unlike [`Event::encode`], this can handle events of any size, such as large pastes.
+    pub fn encode_to_writer<W>(&self, writer: &mut W, encoding: Encoding) -> io::Result<usize>
+    where
+        W: Write,
+    {
+        // Pastes can be arbitrarily large, so we write them directly instead of buffering them
+        if let Self::Paste(text) = self {
+            return write_paste(text, PasteSanitization::Unchanged, writer);
+        }
+        let max_len = self.max_encoded_len(encoding);
+        if max_len <= STACK_BUF_LEN {
+            let mut buf = [0; STACK_BUF_LEN];
+            let written = self.encode(&mut buf, encoding)?;
+            writer.write_all(&buf[..written])?;
+            Ok(written)
+        } else {
+            let buf = self.encode_to_vec(encoding)?;
+            writer.write_all(&buf)?;
+            Ok(buf.len())
+        }
+    }
+
+    /// Encode the event into a new [`Vec`] using the supplied [`Encoding`] mode.
+    pub fn encode_to_vec(&self, encoding: Encoding) -> io::Result<Vec<u8>> {
+        let mut buf = vec![0; self.max_encoded_len(encoding)];
+        let written = self.encode(&mut buf, encoding)?;
+        buf.truncate(written);
+        Ok(buf)
+    }
+
+    /// Returns the maximum number of bytes needed to encode the event using the supplied
+    /// [`Encoding`] mode. The actual encoded length may be smaller.
+    ///
+    /// Key, mouse, and focus events have a small fixed upper bound. The size of pastes, terminal
+    /// responses, and unknown sequences depends on their contents.
+    pub fn max_encoded_len(&self, encoding: Encoding) -> usize {
+        match self {
+            Self::FocusGained | Self::FocusLost => 3,
+            Self::Key(_) => match encoding {
+                Encoding::Xterm | Encoding::XtermWith(_) | Encoding::ModifyOtherKeys(_) => {
+                    MAX_XTERM_KEY_LEN
+                }
+                Encoding::Kitty(_) => MAX_KITTY_KEY_LEN,
+            },
+            Self::Mouse(_) => MAX_MOUSE_LEN,
+            // ESC [ 2 0 0 ~ text ESC [ 2 0 1 ~
+            Self::Paste(text) => text.len() + 12,
+            // ESC [ 4 8 ; rows ; cols ; height ; width t
+            Self::Resize { .. } => 49,
+            Self::Response(response) => response_max_len(response),
+            Self::Unknown { raw } => raw.len(),
+        }
+    }
+
+    fn to_escape_sequence(&self, buf: &mut [u8], options: XtermOptions) -> io::Result<usize> {
         let mut buf = Cursor::new(buf);
         match self {
             Self::FocusGained => {
@@ -56,25 +376,76 @@ impl Event {
                 buf.write_all(b"\x1B[O")?;
                 Ok(buf.position() as usize)
             }
-            Self::Key(key_event) => encode_key_event(key_event, &mut buf),
-            Self::Mouse(mouse_event) => encode_mouse_event(mouse_event, &mut buf),
-            Self::Paste(text) => {
-                buf.write_all(b"\x1B[200~")?;
-                buf.write_all(text.as_bytes())?;
-                buf.write_all(b"\x1B[201~")?;
+            Self::Key(key_event) => encode_key_event(key_event, options, &mut buf),
+            Self::Mouse(mouse_event) => {
+                encode_mouse_event(mouse_event, options.mouse_encoding, &mut buf)
+            }
+            Self::Paste(text) => write_paste(text, PasteSanitization::Unchanged, &mut buf),
+            Self::Resize {
+                rows,
+                cols,
+                pixel_size,
+            } => {
+                // In-band resize notification: CSI 48 ; rows ; cols ; height ; width t
+                // The pixel size is reported as 0 if it's unknown.
+                let PixelSize { width, height } = pixel_size.unwrap_or(PixelSize {
+                    width: 0,
+                    height: 0,
+                });
+                write!(buf, "\x1B[48;{rows};{cols};{height};{width}t")?;
+                Ok(buf.position() as usize)
+            }
+            Self::Response(response) => encode_response(response, &mut buf),
+            Self::Unknown { raw } => {
+                buf.write_all(raw)?;
                 Ok(buf.position() as usize)
             }
-            Self::Resize { .. } => Err(io::Error::new(
-                io::ErrorKind::Unsupported,
-                "Resize events cannot be encoded",
-            )),
         }
     }
 
-    fn to_kitty_escape_sequence(&self, buf: &mut [u8], flags: KittyFlags) -> io::Result<usize> {
+    /// Encodes the event using the modifyOtherKeys protocol. The options are used for any keys
+    /// that fall back to the legacy encoding.
+    pub(crate) fn to_modify_other_keys_escape_sequence(
+        &self,
+        buf: &mut [u8],
+        level: u8,
+        options: XtermOptions,
+    ) -> io::Result<usize> {
+        let Self::Key(key_event) = self else {
+            return self.to_escape_sequence(buf, options);
+        };
+        let key_event = key_event.normalize_case();
+        let codepoint = match key_event.code {
+            KeyCode::Char(c) => c as u32,
+            KeyCode::Enter => 13,
+            KeyCode::Tab => 9,
+            KeyCode::Backspace => 127,
+            KeyCode::Esc => 27,
+            _ => return self.to_escape_sequence(buf, options),
+        };
+        if key_event.kind != KeyEventKind::Press || !uses_modify_other_keys(&key_event, level) {
+            return self.to_escape_sequence(buf, options);
+        }
+        let mut buf = Cursor::new(buf);
+        write!(
+            buf,
+            "\x1B[27;{};{codepoint}~",
+            u16::from(key_event.modifiers.bits()) + 1
+        )?;
+        Ok(buf.position() as usize)
+    }
+
+    /// Encodes the event using the Kitty protocol. The options are used for any keys that fall
+    /// back to the legacy encoding.
+    pub(crate) fn to_kitty_escape_sequence(
+        &self,
+        buf: &mut [u8],
+        flags: KittyFlags,
+        options: XtermOptions,
+    ) -> io::Result<usize> {
         match self {
-            Self::Key(key_event) => self.encode_kitty_key_event(buf, key_event, flags),
-            _ => self.to_escape_sequence(buf),
+            Self::Key(key_event) => self.encode_kitty_key_event(buf, key_event, flags, options),
+            _ => self.to_escape_sequence(buf, options),
         }
     }
 
@@ -83,11 +454,12 @@ impl Event {
         buf: &mut [u8],
         key_event: &KeyEvent,
         flags: KittyFlags,
+        options: XtermOptions,
     ) -> io::Result<usize> {
         if !flags.intersects(
             KittyFlags::DISAMBIGUATE_ESCAPE_CODES | KittyFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES,
         ) {
-            return self.to_escape_sequence(buf);
+            return self.to_escape_sequence(buf, options);
         }
 
         // If this flag is disabled, normal text keys with no special modifiers should use
@@ -103,7 +475,14 @@ impl Event {
             )
             && matches!(key_event.code, KeyCode::Char(_))
         {
-            return self.to_escape_sequence(buf);
+            // The text is sent as-is, which may differ from the key code when using compose
+            // sequences or dead keys
+            if let Some(text) = key_event.text {
+                let mut buf = Cursor::new(buf);
+                buf.write_all(text.as_bytes())?;
+                return Ok(buf.position() as usize);
+            }
+            return self.to_escape_sequence(buf, options);
         }
 
         let key_event = key_event.normalize_case();
@@ -130,7 +509,7 @@ impl Event {
             if key_event.kind == KeyEventKind::Press && !matches!(key_event.code, KeyCode::F(1..=4))
             {
                 buf.set_position(0);
-                let pos = self.to_escape_sequence(buf.get_mut())?;
+                let pos = self.to_escape_sequence(buf.get_mut(), options)?;
                 return Ok(pos);
             }
             write_keycode_suffix(key_event.code, key_event.modifiers, false, &mut buf)?;
@@ -165,7 +544,11 @@ impl Event {
     }
 }
 
-fn encode_key_event(key_event: &KeyEvent, buf: &mut Cursor<&mut [u8]>) -> io::Result<usize> {
+fn encode_key_event(
+    key_event: &KeyEvent,
+    options: XtermOptions,
+    buf: &mut Cursor<&mut [u8]>,
+) -> io::Result<usize> {
     let key_event = key_event.normalize_case();
     if key_event.kind != KeyEventKind::Press {
         return Err(io::Error::new(
@@ -174,6 +557,24 @@ fn encode_key_event(key_event: &KeyEvent, buf: &mut Cursor<&mut [u8]>) -> io::Re
         ));
     }
 
+    if key_event.modifiers.is_empty() {
+        let is_keypad = key_event.state.intersects(KeyEventState::KEYPAD);
+        let ss3_suffix = match key_event.code {
+            KeyCode::Up if options.application_cursor_keys => Some(b'A'),
+            KeyCode::Down if options.application_cursor_keys => Some(b'B'),
+            KeyCode::Right if options.application_cursor_keys => Some(b'C'),
+            KeyCode::Left if options.application_cursor_keys => Some(b'D'),
+            KeyCode::Home if options.application_cursor_keys => Some(b'H'),
+            KeyCode::End if options.application_cursor_keys => Some(b'F'),
+            code if is_keypad && options.application_keypad => application_keypad_suffix(code),
+            _ => None,
+        };
+        if let Some(suffix) = ss3_suffix {
+            buf.write_all(&[b'\x1B', b'O', suffix])?;
+            return Ok(buf.position() as usize);
+        }
+    }
+
     let is_shift = key_event.modifiers.intersects(KeyModifiers::SHIFT);
     let is_ctrl = key_event.modifiers.intersects(KeyModifiers::CTRL);
     let is_alt = key_event.modifiers.intersects(KeyModifiers::ALT);
@@ -277,16 +678,140 @@ fn encode_key_event(key_event: &KeyEvent, buf: &mut Cursor<&mut [u8]>) -> io::Re
     Ok(buf.position() as usize)
 }
 
-fn encode_mouse_event(mouse_event: &MouseEvent, buf: &mut Cursor<&mut [u8]>) -> io::Result<usize> {
+fn uses_modify_other_keys(key_event: &KeyEvent, level: u8) -> bool {
+    let modifiers = key_event.modifiers;
+    let is_char = matches!(key_event.code, KeyCode::Char(_));
+    // Shift is already applied to printable keys, and Shift+Tab has its own sequence
+    if modifiers.difference(KeyModifiers::SHIFT).is_empty()
+        && (modifiers.is_empty() || is_char || key_event.code == KeyCode::Tab)
+    {
+        return false;
+    }
+    match level {
+        0 => false,
+        1 => {
+            // The legacy encoding can't represent these modifiers at all
+            if modifiers.intersects(KeyModifiers::SUPER | KeyModifiers::HYPER | KeyModifiers::META)
+            {
+                return true;
+            }
+            // Only keys that map to a well-known control character use the legacy encoding
+            match key_event.code {
+                KeyCode::Char(c) if modifiers.contains(KeyModifiers::CTRL) => {
+                    modifiers.contains(KeyModifiers::SHIFT)
+                        || !matches!(c, 'a'..='z' | ' ' | '4'..='7')
+                }
+                _ => false,
+            }
+        }
+        _ => true,
+    }
+}
+
+fn application_keypad_suffix(key_code: KeyCode) -> Option<u8> {
+    match key_code {
+        KeyCode::Char(c @ '0'..='9') => Some(c as u8 - b'0' + b'p'),
+        KeyCode::Char('*') => Some(b'j'),
+        KeyCode::Char('+') => Some(b'k'),
+        KeyCode::Char(',') => Some(b'l'),
+        KeyCode::Char('-') => Some(b'm'),
+        KeyCode::Char('.') => Some(b'n'),
+        KeyCode::Char('/') => Some(b'o'),
+        KeyCode::Char('=') => Some(b'X'),
+        KeyCode::Enter => Some(b'M'),
+        _ => None,
+    }
+}
+
+fn encode_mouse_event(
+    mouse_event: &MouseEvent,
+    encoding: MouseEncoding,
+    buf: &mut Cursor<&mut [u8]>,
+) -> io::Result<usize> {
+    let mut base = mouse_button_code(mouse_event)?;
+    let is_release = matches!(mouse_event.kind, MouseEventKind::Up(_));
+    // Positions are one-based, so u16::MAX needs a wider type
+    let column = u32::from(mouse_event.column) + 1;
+    let row = u32::from(mouse_event.row) + 1;
+    match encoding {
+        MouseEncoding::X10 | MouseEncoding::Utf8 | MouseEncoding::Urxvt if is_release => {
+            // These encodings can't report which button was released
+            base = (base & !0b1100_0011) | 3;
+        }
+        _ => {}
+    }
+    match encoding {
+        MouseEncoding::X10 => {
+            let encode_value = |value: u32| {
+                u8::try_from(value + 32).map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "mouse position is too large for the X10 mouse encoding",
+                    )
+                })
+            };
+            buf.write_all(b"\x1B[M")?;
+            buf.write_all(&[base + 32, encode_value(column)?, encode_value(row)?])?;
+        }
+        MouseEncoding::Utf8 => {
+            buf.write_all(b"\x1B[M")?;
+            for value in [u32::from(base), column, row] {
+                // Xterm only uses one or two byte characters for this encoding
+                let c = char::from_u32(value + 32)
+                    .filter(|c| c.len_utf8() <= 2)
+                    .ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "mouse position is too large for the UTF-8 mouse encoding",
+                        )
+                    })?;
+                buf.write_all(c.encode_utf8(&mut [0; 4]).as_bytes())?;
+            }
+        }
+        MouseEncoding::Urxvt => {
+            write!(buf, "\x1B[{};{column};{row}M", base + 32)?;
+        }
+        MouseEncoding::Sgr => {
+            let suffix = if is_release { 'm' } else { 'M' };
+            write!(buf, "\x1B[<{base};{column};{row}{suffix}")?;
+        }
+        MouseEncoding::SgrPixels => {
+            let position = mouse_event.pixel_position.ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "the SGR-Pixels mouse encoding requires a pixel position",
+                )
+            })?;
+            let suffix = if is_release { 'm' } else { 'M' };
+            write!(
+                buf,
+                "\x1B[<{base};{};{}{suffix}",
+                u32::from(position.x) + 1,
+                u32::from(position.y) + 1
+            )?;
+        }
+    }
+    Ok(buf.position() as usize)
+}
+
+fn mouse_button_code(mouse_event: &MouseEvent) -> io::Result<u8> {
+    let button_code = |button| match button {
+        MouseButton::Left | MouseButton::Unknown => Ok(0),
+        MouseButton::Middle => Ok(1),
+        MouseButton::Right => Ok(2),
+        // Buttons 8-11 are reported with the 128 bit set
+        MouseButton::Back => Ok(128),
+        MouseButton::Forward => Ok(129),
+        MouseButton::Other(button @ 8..=11) => Ok(button + 120),
+        MouseButton::Other(_) => Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "only mouse buttons 1-3 and 8-11 can be encoded",
+        )),
+    };
     let mut base = match mouse_event.kind {
         MouseEventKind::Moved => 35,
-        MouseEventKind::Down(MouseButton::Left | MouseButton::Unknown)
-        | MouseEventKind::Up(MouseButton::Left | MouseButton::Unknown) => 0,
-        MouseEventKind::Down(MouseButton::Middle) | MouseEventKind::Up(MouseButton::Middle) => 1,
-        MouseEventKind::Down(MouseButton::Right) | MouseEventKind::Up(MouseButton::Right) => 2,
-        MouseEventKind::Drag(MouseButton::Left | MouseButton::Unknown) => 32,
-        MouseEventKind::Drag(MouseButton::Middle) => 33,
-        MouseEventKind::Drag(MouseButton::Right) => 34,
+        MouseEventKind::Down(button) | MouseEventKind::Up(button) => button_code(button)?,
+        MouseEventKind::Drag(button) => button_code(button)? + 32,
         MouseEventKind::ScrollDown => 65,
         MouseEventKind::ScrollUp => 64,
         MouseEventKind::ScrollLeft => 66,
@@ -301,19 +826,84 @@ fn encode_mouse_event(mouse_event: &MouseEvent, buf: &mut Cursor<&mut [u8]>) ->
     if mouse_event.modifiers.intersects(KeyModifiers::CTRL) {
         base += 16;
     }
-    buf.write_all(b"\x1B[<")?;
-    buf.write_all(base.to_string().as_bytes())?;
-    buf.write_all(b";")?;
-    buf.write_all((mouse_event.column + 1).to_string().as_bytes())?;
-    buf.write_all(b";")?;
-    buf.write_all((mouse_event.row + 1).to_string().as_bytes())?;
+    Ok(base)
+}
+
+pub(crate) fn write_paste<W>(
+    text: &str,
+    sanitization: PasteSanitization,
+    writer: &mut W,
+) -> io::Result<usize>
+where
+    W: Write,
+{
+    let text = sanitization.sanitize(text);
+    writer.write_all(b"\x1B[200~")?;
+    writer.write_all(text.as_bytes())?;
+    writer.write_all(b"\x1B[201~")?;
+    Ok(text.len() + 12)
+}
 
-    if matches!(mouse_event.kind, MouseEventKind::Up(_)) {
-        buf.write_all(b"m")?;
-    } else {
-        buf.write_all(b"M")?;
+fn response_max_len(response: &TerminalResponse) -> usize {
+    match response {
+        // ESC [ ? Ps ; ... c
+        TerminalResponse::PrimaryDeviceAttributes { features, .. } => 9 + 6 * features.len(),
+        // ESC [ > Pp ; Pv ; Pc c
+        TerminalResponse::SecondaryDeviceAttributes { .. } => 26,
+        // ESC [ row ; column R
+        TerminalResponse::CursorPosition { .. } => 14,
+        // ESC [ ? flags u
+        TerminalResponse::KittyKeyboardFlags { .. } => 7,
+        // ESC P > | text ESC \
+        TerminalResponse::TerminalVersion { version } => version.len() + 6,
     }
+}
 
+fn encode_response(response: &TerminalResponse, buf: &mut Cursor<&mut [u8]>) -> io::Result<usize> {
+    match response {
+        TerminalResponse::PrimaryDeviceAttributes {
+            conformance_level,
+            features,
+        } => {
+            buf.write_all(b"\x1B[?")?;
+            buf.write_all(conformance_level.to_string().as_bytes())?;
+            for feature in features {
+                buf.write_all(b";")?;
+                buf.write_all(feature.to_string().as_bytes())?;
+            }
+            buf.write_all(b"c")?;
+        }
+        TerminalResponse::SecondaryDeviceAttributes {
+            terminal_type,
+            firmware_version,
+            rom_cartridge,
+        } => {
+            buf.write_all(b"\x1B[>")?;
+            buf.write_all(terminal_type.to_string().as_bytes())?;
+            buf.write_all(b";")?;
+            buf.write_all(firmware_version.to_string().as_bytes())?;
+            buf.write_all(b";")?;
+            buf.write_all(rom_cartridge.to_string().as_bytes())?;
+            buf.write_all(b"c")?;
+        }
+        TerminalResponse::CursorPosition { row, column } => {
+            buf.write_all(b"\x1B[")?;
+            buf.write_all((u32::from(*row) + 1).to_string().as_bytes())?;
+            buf.write_all(b";")?;
+            buf.write_all((u32::from(*column) + 1).to_string().as_bytes())?;
+            buf.write_all(b"R")?;
+        }
+        TerminalResponse::KittyKeyboardFlags { flags } => {
+            buf.write_all(b"\x1B[?")?;
+            buf.write_all(flags.bits().to_string().as_bytes())?;
+            buf.write_all(b"u")?;
+        }
+        TerminalResponse::TerminalVersion { version } => {
+            buf.write_all(b"\x1BP>|")?;
+            buf.write_all(version.as_bytes())?;
+            buf.write_all(b"\x1B\\")?;
+        }
+    }
     Ok(buf.position() as usize)
 }
 
@@ -327,13 +917,21 @@ fn write_kitty_modifiers(
     let extra_modifiers = key_event
         .state
         .intersection(KeyEventState::CAPS_LOCK | KeyEventState::NUM_LOCK);
+    // Text is only included in CSI-u sequences and never for release events
+    let text = key_event.text.filter(|_| {
+        flags.intersects(KittyFlags::REPORT_ASSOCIATED_TEXT)
+            && trailing_char == b'u'
+            && key_event.kind != KeyEventKind::Release
+    });
 
     if !key_event.modifiers.is_empty()
         || !extra_modifiers.is_empty()
         || (key_event.kind != KeyEventKind::Press && report_event_types)
+        || text.is_some()
     {
         buf.write_all(b";")?;
-        let modifier_sum = key_event.modifiers.bits() + (extra_modifiers.bits() << 5) + 1;
+        let modifier_sum =
+            u16::from(key_event.modifiers.bits()) + (u16::from(extra_modifiers.bits()) << 5) + 1;
         buf.write_all(&modifier_sum.to_string().into_bytes())?;
     }
     if report_event_types {
@@ -347,6 +945,15 @@ fn write_kitty_modifiers(
             KeyEventKind::Press => {}
         };
     }
+    if let Some(text) = text {
+        buf.write_all(b";")?;
+        for (i, c) in text.chars().enumerate() {
+            if i > 0 {
+                buf.write_all(b":")?;
+            }
+            buf.write_all(&(c as u32).to_string().into_bytes())?;
+        }
+    }
     buf.write_all(&[trailing_char])?;
     Ok(())
 }
@@ -526,16 +1133,28 @@ fn write_kitty_encoding(
         KeyCode::Char(c) => {
             // We should always use the lower-cased key for the first value
             let c = c.to_ascii_lowercase();
-            convert_suffix_code(KeyCode::Char(c), key_event.modifiers, buf)?;
-            if flags.intersects(KittyFlags::REPORT_ALTERNATE_KEYS)
-                && key_event.modifiers.intersects(KeyModifiers::SHIFT)
-            {
-                // Ideally we could do this for other chars besides just ascii,
-                // but that requires knowing the keyboard layout
-                let upper = c.to_ascii_uppercase();
-                if upper != c {
+            buf.write_all(&(c as u32).to_string().into_bytes())?;
+            if flags.intersects(KittyFlags::REPORT_ALTERNATE_KEYS) {
+                // The shifted key is only sent if shift is pressed. If it wasn't supplied, we
+                // can only infer it for ascii chars since other chars require knowing the
+                // keyboard layout.
+                let shifted_key = key_event
+                    .shifted_key
+                    .or_else(|| {
+                        let upper = c.to_ascii_uppercase();
+                        (upper != c).then_some(upper)
+                    })
+                    .filter(|_| key_event.modifiers.intersects(KeyModifiers::SHIFT));
+                if let Some(shifted_key) = shifted_key {
                     buf.write_all(b":")?;
-                    buf.write_all(&(upper as u8).to_string().into_bytes())?;
+                    buf.write_all(&(shifted_key as u32).to_string().into_bytes())?;
+                }
+                if let Some(base_layout_key) = key_event.base_layout_key {
+                    if shifted_key.is_none() {
+                        buf.write_all(b":")?;
+                    }
+                    buf.write_all(b":")?;
+                    buf.write_all(&(base_layout_key as u32).to_string().into_bytes())?;
                 }
             }
         }
@@ -584,11 +1203,7 @@ fn write_keycode_suffix(
         KeyCode::F(10) => buf.write_all(b"21~"),
         KeyCode::F(11) => buf.write_all(b"23~"),
         KeyCode::F(12) => buf.write_all(b"24~"),
-        KeyCode::Char(c) => {
-            let pos = buf.position() as usize;
-            let len = c.encode_utf8(&mut buf.get_mut()[pos..]).len();
-            buf.seek_relative(len as i64)
-        }
+        KeyCode::Char(c) => buf.write_all(c.encode_utf8(&mut [0; 4]).as_bytes()),
         KeyCode::Esc => buf.write_all(b"\x1B"),
         _ => return Ok(false),
     }?;
@@ -1,9 +1,12 @@
+use std::cmp::Ordering;
+use std::fmt;
 use std::hash::{Hash, Hasher};
+use std::ops::Deref;
 
 use bitflags::bitflags;
 
 /// A key input event.
-#[derive(Debug, PartialOrd, Ord, Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
 pub struct KeyEvent {
     /// The key code.
     pub code: KeyCode,
@@ -13,6 +16,21 @@ pub struct KeyEvent {
     pub kind: KeyEventKind,
     /// Other keyboard properties.
     pub state: KeyEventState,
+    /// The text generated by the key event, if reported by the terminal. This may differ from
+    /// the key code when using dead keys, compose sequences, or input methods.
+    ///
+    /// This is not considered when comparing or hashing key events.
+    pub text: Option<KeyText>,
+    /// The character produced by the key when shift is held, according to the current keyboard
+    /// layout. This is only reported when the shift modifier is active.
+    ///
+    /// This is not considered when comparing or hashing key events.
+    pub shifted_key: Option<char>,
+    /// The key at the same physical position on a standard PC-101 (US) keyboard layout. This can
+    /// be used to match shortcuts by position regardless of the active keyboard layout.
+    ///
+    /// This is not considered when comparing or hashing key events.
+    pub base_layout_key: Option<char>,
 }
 
 impl KeyEvent {
@@ -23,6 +41,9 @@ impl KeyEvent {
             modifiers: KeyModifiers::empty(),
             kind: KeyEventKind::Press,
             state: KeyEventState::empty(),
+            text: None,
+            shifted_key: None,
+            base_layout_key: None,
         }
     }
 
@@ -44,6 +65,24 @@ impl KeyEvent {
         self
     }
 
+    /// Sets the associated [`KeyText`].
+    pub const fn text(mut self, text: KeyText) -> Self {
+        self.text = Some(text);
+        self
+    }
+
+    /// Sets the [shifted key](KeyEvent::shifted_key).
+    pub const fn shifted_key(mut self, shifted_key: char) -> Self {
+        self.shifted_key = Some(shifted_key);
+        self
+    }
+
+    /// Sets the [base layout key](KeyEvent::base_layout_key).
+    pub const fn base_layout_key(mut self, base_layout_key: char) -> Self {
+        self.base_layout_key = Some(base_layout_key);
+        self
+    }
+
     /// Normalizes the event so the shift modifier is applied appropriately.
     pub fn normalize_case(mut self) -> Self {
         let c = match self.code {
@@ -58,6 +97,134 @@ impl KeyEvent {
         }
         self
     }
+
+    /// Returns the key event that would be reported for this key by a terminal using the legacy
+    /// protocol, or [`None`] if legacy terminals can't report the key at all.
+    ///
+    /// Terminals that support the kitty keyboard protocol or `modifyOtherKeys` can report keys
+    /// that legacy terminals send as the same bytes. This maps each key to the one that
+    /// [`parse_event`](crate::parse_event) returns for those bytes, so keys that legacy terminals
+    /// can't tell apart have the same equivalent. For example:
+    ///
+    /// - `Ctrl+I` and `Ctrl+Tab` are reported as `Tab`.
+    /// - `Ctrl+M`, `Ctrl+Enter`, and `Shift+Enter` are reported as `Enter`.
+    /// - `Ctrl+[` and `Ctrl+3` are reported as `Esc`.
+    /// - `Ctrl+Backspace` is reported as `Ctrl+H`, since both are sent as `\x08`.
+    /// - `Ctrl+Shift+A` is reported as `Ctrl+A`.
+    ///
+    /// Lock states and the keypad state are removed, and repeats are treated as presses. Keys
+    /// that legacy terminals can't send return [`None`] rather than a different key. This
+    /// includes key releases, media keys, modifier keys, keys such as Caps Lock and Print Screen,
+    /// keys with the super, hyper, or meta modifiers, control combined with a character that has
+    /// no control code, such as `Ctrl+1`, and shift combined with a symbol when the
+    /// [shifted key](KeyEvent::shifted_key) isn't known, such as `Shift+1`.
+    ///
+    /// ```
+    /// use terminput::{KeyCode, KeyEvent, KeyModifiers};
+    ///
+    /// let ctrl_i = KeyEvent::new(KeyCode::Char('i')).modifiers(KeyModifiers::CTRL);
+    /// assert_eq!(
+    ///     ctrl_i.legacy_equivalent(),
+    ///     Some(KeyEvent::new(KeyCode::Tab))
+    /// );
+    /// assert!(ctrl_i.legacy_conflicts_with(&KeyEvent::new(KeyCode::Tab)));
+    /// ```
+    pub fn legacy_equivalent(&self) -> Option<Self> {
+        let key_event = self.normalize_case();
+        if key_event.kind == KeyEventKind::Release
+            || key_event
+                .modifiers
+                .intersects(KeyModifiers::SUPER | KeyModifiers::HYPER | KeyModifiers::META)
+        {
+            return None;
+        }
+        let mut modifiers = key_event.modifiers;
+        let ctrl = modifiers.contains(KeyModifiers::CTRL);
+        let code = match key_event.code {
+            KeyCode::CapsLock
+            | KeyCode::ScrollLock
+            | KeyCode::NumLock
+            | KeyCode::PrintScreen
+            | KeyCode::Pause
+            | KeyCode::Menu
+            | KeyCode::Media(_)
+            | KeyCode::Modifier(..) => return None,
+            code @ (KeyCode::Enter | KeyCode::Esc) => {
+                modifiers -= KeyModifiers::CTRL | KeyModifiers::SHIFT;
+                code
+            }
+            KeyCode::Tab => {
+                modifiers -= KeyModifiers::CTRL;
+                KeyCode::Tab
+            }
+            KeyCode::Backspace if ctrl => {
+                modifiers -= KeyModifiers::SHIFT;
+                KeyCode::Char('h')
+            }
+            KeyCode::Backspace => {
+                modifiers -= KeyModifiers::SHIFT;
+                KeyCode::Backspace
+            }
+            KeyCode::Char(c) => {
+                // Only letters are reported with shift
+                let c = if c.is_ascii_alphabetic() || !modifiers.contains(KeyModifiers::SHIFT) {
+                    c
+                } else {
+                    modifiers -= KeyModifiers::SHIFT;
+                    key_event.shifted_key?
+                };
+                if ctrl {
+                    legacy_ctrl_char(c, &mut modifiers)?
+                } else {
+                    KeyCode::Char(c)
+                }
+            }
+            code => code,
+        };
+        Some(Self::new(code).modifiers(modifiers))
+    }
+
+    /// Returns whether the key events are different, but are reported as the same key by
+    /// terminals using the legacy protocol. See [`KeyEvent::legacy_equivalent`].
+    ///
+    /// This can be used to warn users about key bindings that won't work as expected unless the
+    /// terminal supports a more capable protocol.
+    pub fn legacy_conflicts_with(&self, other: &Self) -> bool {
+        self != other
+            && self
+                .legacy_equivalent()
+                .is_some_and(|key_event| Some(key_event) == other.legacy_equivalent())
+    }
+}
+
+// Maps a character combined with control to the key that's reported for the control character
+// that legacy terminals send for it, or `None` if there's no control character for it
+fn legacy_ctrl_char(c: char, modifiers: &mut KeyModifiers) -> Option<KeyCode> {
+    let ctrl_char = match c.to_ascii_lowercase() {
+        'i' => Some(KeyCode::Tab),
+        'm' => Some(KeyCode::Enter),
+        '[' | '3' => Some(KeyCode::Esc),
+        '?' | '8' => Some(KeyCode::Backspace),
+        _ => None,
+    };
+    if let Some(code) = ctrl_char {
+        *modifiers -= KeyModifiers::CTRL | KeyModifiers::SHIFT;
+        return Some(code);
+    }
+    let code = match c {
+        c if c.is_ascii_alphabetic() => {
+            *modifiers -= KeyModifiers::SHIFT;
+            KeyCode::Char(c.to_ascii_lowercase())
+        }
+        '@' | '2' | ' ' => KeyCode::Char(' '),
+        '\\' | '4' => KeyCode::Char('4'),
+        ']' | '5' => KeyCode::Char('5'),
+        '^' | '6' => KeyCode::Char('6'),
+        '_' | '7' | '/' => KeyCode::Char('7'),
+        // Legacy terminals send other characters without control
+        _ => return None,
+    };
+    Some(code)
 }
 
 impl PartialEq for KeyEvent {
@@ -67,12 +234,18 @@ impl PartialEq for KeyEvent {
             modifiers: lhs_modifiers,
             kind: lhs_kind,
             state: lhs_state,
+            text: _,
+            shifted_key: _,
+            base_layout_key: _,
         } = self.normalize_case();
         let Self {
             code: rhs_code,
             modifiers: rhs_modifiers,
             kind: rhs_kind,
             state: rhs_state,
+            text: _,
+            shifted_key: _,
+            base_layout_key: _,
         } = other.normalize_case();
         (lhs_code == rhs_code)
             && (lhs_modifiers == rhs_modifiers)
@@ -83,6 +256,41 @@ impl PartialEq for KeyEvent {
 
 impl Eq for KeyEvent {}
 
+impl PartialOrd for KeyEvent {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for KeyEvent {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let Self {
+            code: lhs_code,
+            modifiers: lhs_modifiers,
+            kind: lhs_kind,
+            state: lhs_state,
+            text: _,
+            shifted_key: _,
+            base_layout_key: _,
+        } = self.normalize_case();
+        let Self {
+            code: rhs_code,
+            modifiers: rhs_modifiers,
+            kind: rhs_kind,
+            state: rhs_state,
+            text: _,
+            shifted_key: _,
+            base_layout_key: _,
+        } = other.normalize_case();
+        (lhs_code, lhs_modifiers, lhs_kind, lhs_state).cmp(&(
+            rhs_code,
+            rhs_modifiers,
+            rhs_kind,
+            rhs_state,
+        ))
+    }
+}
+
 impl Hash for KeyEvent {
     fn hash<H: Hasher>(&self, hash_state: &mut H) {
         let Self {
@@ -90,6 +298,9 @@ impl Hash for KeyEvent {
             modifiers,
             kind,
             state,
+            text: _,
+            shifted_key: _,
+            base_layout_key: _,
         } = self.normalize_case();
         code.hash(hash_state);
         modifiers.hash(hash_state);
@@ -272,11 +483,69 @@ bitflags! {
 
 impl From<KeyCode> for KeyEvent {
     fn from(code: KeyCode) -> Self {
-        Self {
-            code,
-            modifiers: KeyModifiers::empty(),
-            kind: KeyEventKind::Press,
-            state: KeyEventState::empty(),
+        Self::new(code)
+    }
+}
+
+/// Text associated with a [`KeyEvent`].
+///
+/// The text is stored inline so that [`KeyEvent`] can remain [`Copy`], which limits it to
+/// [`KeyText::CAPACITY`] bytes. This is enough for the text generated by a single key press,
+/// including most compose sequences, input method commits, and emoji joined with zero-width
+/// joiners.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct KeyText {
+    buf: [u8; Self::CAPACITY],
+    len: u8,
+}
+
+impl KeyText {
+    /// The maximum length of the text in bytes.
+    pub const CAPACITY: usize = 31;
+
+    /// Creates a new [`KeyText`]. Returns [`None`] if the text is longer than
+    /// [`KeyText::CAPACITY`].
+    pub fn new(text: &str) -> Option<Self> {
+        if text.len() > Self::CAPACITY {
+            return None;
         }
+        let mut buf = [0; Self::CAPACITY];
+        buf[..text.len()].copy_from_slice(text.as_bytes());
+        Some(Self {
+            buf,
+            len: text.len() as u8,
+        })
+    }
+
+    /// Returns the text as a string slice.
+    pub fn as_str(&self) -> &str {
+        // The buffer is always created from a valid string
+        std::str::from_utf8(&self.buf[..self.len as usize]).unwrap_or_default()
+    }
+}
+
+impl Deref for KeyText {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for KeyText {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl fmt::Debug for KeyText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for KeyText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
     }
 }
@@ -0,0 +1,156 @@
+use std::str::FromStr;
+
+use crate::{KeyCode, KeyEvent, KeyEventKind, KeyEventState, ModifierDirection, NotationError};
+
+/// A key binding that matches [`KeyEvent`]s more loosely than [`KeyEvent`]'s [`PartialEq`]
+/// implementation.
+///
+/// Terminals that support the kitty keyboard protocol report extra details that legacy terminals
+/// don't, such as the state of Caps Lock and Num Lock, whether the key is on the keypad, and
+/// repeat events. By default, a pattern ignores these so that bindings behave the same with
+/// either protocol:
+///
+/// - Caps Lock and Num Lock states are ignored.
+/// - A pattern for a key press also matches repeat events.
+/// - Keys on the keypad match the equivalent keys on the main keyboard, so a binding for `1`
+///   matches the `1` on the keypad.
+///
+/// Each of these can be disabled, and the pattern can also be set to match modifier keys from
+/// either side of the keyboard using [`KeyPattern::ignore_modifier_direction`].
+///
+/// ```
+/// use terminput::{KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers, KeyPattern};
+///
+/// let pattern = KeyPattern::new(KeyEvent::new(KeyCode::Char('a')).modifiers(KeyModifiers::CTRL));
+/// assert!(
+///     pattern.matches(
+///         &KeyEvent::new(KeyCode::Char('a'))
+///             .modifiers(KeyModifiers::CTRL)
+///             .kind(KeyEventKind::Repeat)
+///             .state(KeyEventState::NUM_LOCK)
+///     )
+/// );
+///
+/// let pattern: KeyPattern = "ctrl+a".parse().unwrap();
+/// assert!(!pattern.matches(&KeyEvent::new(KeyCode::Char('a'))));
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct KeyPattern {
+    key_event: KeyEvent,
+    ignore_lock_state: bool,
+    match_repeats: bool,
+    ignore_keypad: bool,
+    ignore_modifier_direction: bool,
+    match_legacy_equivalents: bool,
+}
+
+impl From<KeyEvent> for KeyPattern {
+    fn from(key_event: KeyEvent) -> Self {
+        Self::new(key_event)
+    }
+}
+
+impl From<KeyCode> for KeyPattern {
+    fn from(code: KeyCode) -> Self {
+        Self::new(KeyEvent::new(code))
+    }
+}
+
+/// Parses a pattern for a key event in its canonical notation, such as `ctrl+shift+a`. See
+/// [`KeyEvent`]'s [`FromStr`] implementation for details.
+impl FromStr for KeyPattern {
+    type Err = NotationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::new(s.parse()?))
+    }
+}
+
+impl KeyPattern {
+    /// Creates a new [`KeyPattern`] that matches the key event, ignoring lock states, repeats, and
+    /// whether the key is on the keypad.
+    pub fn new(key_event: KeyEvent) -> Self {
+        Self {
+            key_event: key_event.normalize_case(),
+            ignore_lock_state: true,
+            match_repeats: true,
+            ignore_keypad: true,
+            ignore_modifier_direction: false,
+            match_legacy_equivalents: false,
+        }
+    }
+
+    /// Sets whether the Caps Lock and Num Lock states are ignored. Enabled by default.
+    pub const fn ignore_lock_state(mut self, ignore_lock_state: bool) -> Self {
+        self.ignore_lock_state = ignore_lock_state;
+        self
+    }
+
+    /// Sets whether a pattern for a key press also matches repeat events. Enabled by default.
+    pub const fn match_repeats(mut self, match_repeats: bool) -> Self {
+        self.match_repeats = match_repeats;
+        self
+    }
+
+    /// Sets whether keys on the keypad are treated the same as the equivalent keys on the main
+    /// keyboard. Enabled by default.
+    pub const fn ignore_keypad(mut self, ignore_keypad: bool) -> Self {
+        self.ignore_keypad = ignore_keypad;
+        self
+    }
+
+    /// Sets whether modifier keys from the left and right sides of the keyboard are treated the
+    /// same, such as [`ModifierKeyCode::Shift`](crate::ModifierKeyCode::Shift) with
+    /// [`ModifierDirection::Left`] and [`ModifierDirection::Right`]. Disabled by default.
+    pub const fn ignore_modifier_direction(mut self, ignore_modifier_direction: bool) -> Self {
+        self.ignore_modifier_direction = ignore_modifier_direction;
+        self
+    }
+
+    /// Sets whether keys that terminals using the legacy protocol can't tell apart are treated the
+    /// same, such as `Ctrl+I` and `Tab`. See [`KeyEvent::legacy_equivalent`]. Disabled by default.
+    ///
+    /// This makes the pattern match the same keys regardless of which protocol the terminal uses.
+    /// Keys that legacy terminals can't report, such as media keys or keys with the super modifier,
+    /// are still matched exactly.
+    pub const fn match_legacy_equivalents(mut self, match_legacy_equivalents: bool) -> Self {
+        self.match_legacy_equivalents = match_legacy_equivalents;
+        self
+    }
+
+    /// Returns the key event that the pattern matches.
+    pub const fn key_event(&self) -> &KeyEvent {
+        &self.key_event
+    }
+
+    /// Returns whether the key event matches the pattern.
+    pub fn matches(&self, key_event: &KeyEvent) -> bool {
+        self.normalize(&self.key_event) == self.normalize(key_event)
+    }
+
+    // Removes the details of the key event that the pattern ignores
+    fn normalize(&self, key_event: &KeyEvent) -> KeyEvent {
+        let mut key_event = key_event.normalize_case();
+        // Keys that legacy terminals can't send have no equivalent, so they're matched exactly
+        // rather than being reduced to a different key
+        if let (true, Some(legacy)) = (self.match_legacy_equivalents, key_event.legacy_equivalent())
+        {
+            key_event = legacy;
+        }
+        if self.ignore_lock_state {
+            key_event.state -= KeyEventState::CAPS_LOCK | KeyEventState::NUM_LOCK;
+        }
+        if self.ignore_keypad {
+            key_event.state -= KeyEventState::KEYPAD;
+        }
+        if self.match_repeats && key_event.kind == KeyEventKind::Repeat {
+            key_event.kind = KeyEventKind::Press;
+        }
+        if let (true, KeyCode::Modifier(modifier, _)) =
+            (self.ignore_modifier_direction, key_event.code)
+        {
+            key_event.code = KeyCode::Modifier(modifier, ModifierDirection::Unknown);
+        }
+        key_event
+    }
+}
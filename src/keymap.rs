@@ -0,0 +1,426 @@
+use std::mem;
+use std::time::{Duration, Instant};
+
+use crate::{KeyCode, KeyEvent, KeyEventKind, KeyPattern};
+
+/// A set of key bindings that can be used as one of the layers of a [`Keymap`].
+///
+/// Each binding is a sequence of one or more keys that maps to an action. Keys are matched using
+/// [`KeyPattern`], so a binding for a [`KeyEvent`] ignores lock states and also matches repeats
+/// by default. Bind a [`KeyPattern`] directly to change how each key is matched.
+#[derive(Debug, Clone)]
+pub struct KeymapLayer<A> {
+    root: KeymapNode<A>,
+    fallthrough: bool,
+}
+
+#[derive(Debug, Clone)]
+struct KeymapNode<A> {
+    action: Option<A>,
+    children: Vec<(KeyPattern, KeymapNode<A>)>,
+}
+
+impl<A> KeymapNode<A> {
+    const fn new() -> Self {
+        Self {
+            action: None,
+            children: Vec::new(),
+        }
+    }
+
+    fn find(&self, keys: &[KeyEvent]) -> Option<&Self> {
+        keys.iter().try_fold(self, |node, key| {
+            node.children
+                .iter()
+                .find(|(pattern, _)| pattern.matches(key))
+                .map(|(_, child)| child)
+        })
+    }
+}
+
+impl<A> Default for KeymapLayer<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A> KeymapLayer<A> {
+    /// Creates a new, empty [`KeymapLayer`] that doesn't fall through to the layers below it.
+    pub const fn new() -> Self {
+        Self {
+            root: KeymapNode::new(),
+            fallthrough: false,
+        }
+    }
+
+    /// Sets whether keys that aren't bound in this layer are looked up in the layers below it.
+    /// This is disabled by default, so only the top layer is active, like the modes of a modal
+    /// editor.
+    pub const fn fallthrough(mut self, fallthrough: bool) -> Self {
+        self.fallthrough = fallthrough;
+        self
+    }
+
+    /// Binds a sequence of keys to an action, replacing any existing binding for the sequence.
+    pub fn bind<I>(mut self, keys: I, action: A) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<KeyPattern>,
+    {
+        self.insert(keys, action);
+        self
+    }
+
+    /// Binds a sequence of keys to an action and returns the action that was previously bound to
+    /// it. Empty sequences are ignored.
+    pub fn insert<I>(&mut self, keys: I, action: A) -> Option<A>
+    where
+        I: IntoIterator,
+        I::Item: Into<KeyPattern>,
+    {
+        let mut node = &mut self.root;
+        let mut is_empty = true;
+        for key in keys {
+            is_empty = false;
+            let pattern = key.into();
+            let index = match node.children.iter().position(|(p, _)| *p == pattern) {
+                Some(index) => index,
+                None => {
+                    node.children.push((pattern, KeymapNode::new()));
+                    node.children.len() - 1
+                }
+            };
+            node = &mut node.children[index].1;
+        }
+        if is_empty {
+            return None;
+        }
+        node.action.replace(action)
+    }
+
+    /// Returns the action bound to a sequence of keys that matches the given keys, if any.
+    pub fn get(&self, keys: &[KeyEvent]) -> Option<&A> {
+        if keys.is_empty() {
+            return None;
+        }
+        self.root.find(keys).and_then(|node| node.action.as_ref())
+    }
+}
+
+/// The result of feeding a key to a [`Keymap`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum KeymapMatch<A> {
+    /// The keys matched a binding.
+    Complete {
+        /// The action bound to the keys.
+        action: A,
+        /// The count that was typed before the keys, if [counts](Keymap::counts) are enabled.
+        count: Option<usize>,
+    },
+    /// The keys are the start of at least one binding, so more keys are needed. If the keys also
+    /// match a binding on their own, it's used once the [timeout](Keymap::timeout) expires or a
+    /// key that doesn't continue the sequence arrives.
+    Pending,
+    /// The keys don't match any binding.
+    NoMatch {
+        /// The keys that were discarded, including any count.
+        keys: Vec<KeyEvent>,
+    },
+}
+
+/// Matches sequences of keys against layers of key bindings, such as `g g` or
+/// `Ctrl+X Ctrl+S`.
+///
+/// Keys are fed in one at a time using [`Keymap::process`], which reports whether they completed
+/// a binding, started one, or didn't match anything. When a sequence is bound and is also the start
+/// of a longer binding, such as `g` and `g g`, the keymap waits for the next key to decide between
+/// them. If a [timeout](Keymap::timeout) is set, it also stops waiting once the
+/// [deadline](Keymap::deadline) passes, after which [`Keymap::flush`] should be called.
+///
+/// The keymap contains a stack of [layers](KeymapLayer), with the last one pushed being checked
+/// first. This can be used for modal interfaces by pushing a layer when entering a mode and popping
+/// it when leaving.
+///
+/// ```
+/// use terminput::{KeyCode, KeyEvent, KeyModifiers, Keymap, KeymapLayer, KeymapMatch};
+///
+/// #[derive(Debug, Clone, PartialEq)]
+/// enum Action {
+///     Save,
+///     Top,
+/// }
+///
+/// let ctrl = |c| KeyEvent::new(KeyCode::Char(c)).modifiers(KeyModifiers::CTRL);
+/// let g = KeyEvent::new(KeyCode::Char('g'));
+/// let layer = KeymapLayer::new()
+///     .bind([ctrl('x'), ctrl('s')], Action::Save)
+///     .bind([g, g], Action::Top);
+/// let mut keymap = Keymap::new(layer).counts(true);
+///
+/// assert_eq!(keymap.process(ctrl('x')), vec![KeymapMatch::Pending]);
+/// assert_eq!(
+///     keymap.process(ctrl('s')),
+///     vec![KeymapMatch::Complete {
+///         action: Action::Save,
+///         count: None,
+///     }]
+/// );
+///
+/// keymap.process(KeyEvent::new(KeyCode::Char('5')));
+/// keymap.process(g);
+/// assert_eq!(
+///     keymap.process(g),
+///     vec![KeymapMatch::Complete {
+///         action: Action::Top,
+///         count: Some(5),
+///     }]
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Keymap<A> {
+    layers: Vec<KeymapLayer<A>>,
+    timeout: Option<Duration>,
+    ignore_releases: bool,
+    counts: bool,
+    // The keys that could be part of a binding
+    pending: Vec<KeyEvent>,
+    // The keys that were used to type the count
+    count_keys: Vec<KeyEvent>,
+    count: Option<usize>,
+    last_key: Option<Instant>,
+}
+
+impl<A> Keymap<A>
+where
+    A: Clone,
+{
+    /// Creates a new [`Keymap`] with the given base layer. By default, there's no timeout, counts
+    /// are disabled, and key releases are matched like any other key.
+    pub fn new(layer: KeymapLayer<A>) -> Self {
+        Self {
+            layers: vec![layer],
+            timeout: None,
+            ignore_releases: false,
+            counts: false,
+            pending: Vec::new(),
+            count_keys: Vec::new(),
+            count: None,
+            last_key: None,
+        }
+    }
+
+    /// Sets the amount of time to wait for the next key in a sequence, the same as Vim's
+    /// `timeoutlen` option.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets whether [`KeyEventKind::Release`] events are ignored. Enable this when the terminal
+    /// reports key releases, but the bindings only use presses.
+    pub fn ignore_releases(mut self, ignore_releases: bool) -> Self {
+        self.ignore_releases = ignore_releases;
+        self
+    }
+
+    /// Sets whether numbers typed before a sequence are treated as a count, such as `3 d d` in
+    /// Vim. The count is included in the [`KeymapMatch::Complete`] result.
+    ///
+    /// A count can't start with `0`, and digits that are bound on their own or as the start of a
+    /// sequence are treated as part of a binding instead, unless they follow another digit of the
+    /// count.
+    pub fn counts(mut self, counts: bool) -> Self {
+        self.counts = counts;
+        self
+    }
+
+    /// Pushes a layer onto the stack. Its bindings take precedence over the ones below it.
+    pub fn push_layer(&mut self, layer: KeymapLayer<A>) {
+        self.layers.push(layer);
+    }
+
+    /// Removes the top layer from the stack and returns it. The base layer can't be removed, so
+    /// this returns [`None`] if it's the only one left.
+    pub fn pop_layer(&mut self) -> Option<KeymapLayer<A>> {
+        if self.layers.len() > 1 {
+            self.layers.pop()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the stack of layers, starting with the base layer.
+    pub fn layers(&self) -> &[KeymapLayer<A>] {
+        &self.layers
+    }
+
+    /// Returns the keys of a sequence that's waiting for more keys, not including the count.
+    pub fn pending(&self) -> &[KeyEvent] {
+        &self.pending
+    }
+
+    /// Returns the count that's been typed so far, if any.
+    pub fn count(&self) -> Option<usize> {
+        self.count
+    }
+
+    /// Discards the pending keys and count.
+    pub fn clear(&mut self) {
+        self.pending.clear();
+        self.count_keys.clear();
+        self.count = None;
+        self.last_key = None;
+    }
+
+    /// Returns the time at which the pending keys should be [flushed](Keymap::flush) if no more
+    /// keys arrive. Returns [`None`] if there's no timeout or no keys are pending.
+    pub fn deadline(&self) -> Option<Instant> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        Some(self.last_key? + self.timeout?)
+    }
+
+    /// Feeds a key to the keymap and returns the results.
+    ///
+    /// Usually there's a single result, but a key can also complete an earlier binding and then
+    /// start a new sequence, such as when `g` and `g g` are both bound and `g` is followed by `x`.
+    /// If the keymap is still waiting for more keys afterwards, the last result is
+    /// [`KeymapMatch::Pending`]. If the [deadline](Keymap::deadline) has already passed, the
+    /// pending keys are flushed before the new key is processed. Ignored key releases don't
+    /// produce any results.
+    pub fn process(&mut self, key_event: KeyEvent) -> Vec<KeymapMatch<A>> {
+        let mut results = Vec::new();
+        if self.ignore_releases && key_event.kind == KeyEventKind::Release {
+            return results;
+        }
+        let now = Instant::now();
+        if self.deadline().is_some_and(|deadline| deadline < now) {
+            self.flush_into(&mut results);
+        }
+        self.feed(key_event.normalize_case(), &mut results);
+        if self.is_pending() {
+            self.last_key = Some(now);
+            results.push(KeymapMatch::Pending);
+        }
+        results
+    }
+
+    /// Resolves the pending keys as if no more keys will arrive. A sequence that's bound on its
+    /// own is matched, and any other keys are returned as [`KeymapMatch::NoMatch`].
+    pub fn flush(&mut self) -> Vec<KeymapMatch<A>> {
+        let mut results = Vec::new();
+        self.flush_into(&mut results);
+        if !self.count_keys.is_empty() {
+            results.push(KeymapMatch::NoMatch {
+                keys: mem::take(&mut self.count_keys),
+            });
+        }
+        self.clear();
+        results
+    }
+
+    fn is_pending(&self) -> bool {
+        !self.pending.is_empty() || self.count.is_some()
+    }
+
+    fn feed(&mut self, key_event: KeyEvent, results: &mut Vec<KeymapMatch<A>>) {
+        if self.pending.is_empty() {
+            if let Some(digit) = self.count_digit(&key_event) {
+                self.count = Some(
+                    self.count
+                        .unwrap_or_default()
+                        .saturating_mul(10)
+                        .saturating_add(digit),
+                );
+                self.count_keys.push(key_event);
+                return;
+            }
+        }
+
+        self.pending.push(key_event);
+        let (action, is_prefix) = self.lookup(&self.pending);
+        if is_prefix {
+            return;
+        }
+        if let Some(action) = action {
+            self.complete(action, results);
+            self.pending.clear();
+            return;
+        }
+
+        self.resolve_pending(results);
+    }
+
+    fn flush_into(&mut self, results: &mut Vec<KeymapMatch<A>>) {
+        while !self.pending.is_empty() {
+            self.resolve_pending(results);
+        }
+    }
+
+    // Uses the longest bound sequence at the start of the pending keys, then feeds the rest
+    // again. If none of them are bound, they're all discarded.
+    fn resolve_pending(&mut self, results: &mut Vec<KeymapMatch<A>>) {
+        let bound = (1..=self.pending.len())
+            .rev()
+            .find_map(|len| Some((len, self.lookup(&self.pending[..len]).0?)));
+        let Some((len, action)) = bound else {
+            let mut keys = mem::take(&mut self.count_keys);
+            keys.append(&mut self.pending);
+            self.count = None;
+            results.push(KeymapMatch::NoMatch { keys });
+            return;
+        };
+        let rest = self.pending.split_off(len);
+        self.pending.clear();
+        self.complete(action, results);
+        for key_event in rest {
+            self.feed(key_event, results);
+        }
+    }
+
+    fn complete(&mut self, action: A, results: &mut Vec<KeymapMatch<A>>) {
+        self.count_keys.clear();
+        results.push(KeymapMatch::Complete {
+            action,
+            count: self.count.take(),
+        });
+    }
+
+    fn count_digit(&self, key_event: &KeyEvent) -> Option<usize> {
+        if !self.counts
+            || !key_event.modifiers.is_empty()
+            || key_event.kind == KeyEventKind::Release
+        {
+            return None;
+        }
+        let KeyCode::Char(c) = key_event.code else {
+            return None;
+        };
+        let digit = c.to_digit(10)?;
+        if self.count.is_none()
+            && (digit == 0 || !matches!(self.lookup(&[*key_event]), (None, false)))
+        {
+            return None;
+        }
+        Some(digit as usize)
+    }
+
+    // Returns the action bound to the keys and whether they're the start of a longer binding
+    fn lookup(&self, keys: &[KeyEvent]) -> (Option<A>, bool) {
+        let mut action = None;
+        let mut is_prefix = false;
+        for layer in self.layers.iter().rev() {
+            if let Some(node) = layer.root.find(keys) {
+                if action.is_none() {
+                    action.clone_from(&node.action);
+                }
+                is_prefix |= !node.children.is_empty();
+            }
+            if !layer.fallthrough {
+                break;
+            }
+        }
+        (action, is_prefix)
+    }
+}
@@ -17,8 +17,14 @@ mod crossterm;
 mod egui;
 mod encoder;
 mod key;
+mod key_pattern;
+mod keymap;
+mod mode_encoder;
 mod mouse;
+mod notation;
 mod parser;
+mod paste_detector;
+mod response;
 #[cfg(feature = "termion")]
 mod termion;
 #[cfg(feature = "termwiz")]
@@ -29,8 +35,14 @@ use std::error::Error;
 
 pub use encoder::*;
 pub use key::*;
+pub use key_pattern::*;
+pub use keymap::*;
+pub use mode_encoder::*;
 pub use mouse::*;
+pub use notation::*;
 pub use parser::*;
+pub use paste_detector::*;
+pub use response::*;
 
 /// The supplied event could not be converted into the requested type.
 #[derive(Debug)]
@@ -44,6 +56,15 @@ impl fmt::Display for UnsupportedEvent {
 
 impl Error for UnsupportedEvent {}
 
+/// The size of the terminal's text area in pixels.
+#[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct PixelSize {
+    /// The width in pixels.
+    pub width: u32,
+    /// The height in pixels.
+    pub height: u32,
+}
+
 /// An application event.
 #[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Hash)]
 pub enum Event {
@@ -63,5 +84,30 @@ pub enum Event {
         rows: u32,
         /// New number of columns.
         cols: u32,
+        /// New size of the text area in pixels, if it was reported by the input source.
+        pixel_size: Option<PixelSize>,
+    },
+    /// A response to a query that was sent to the terminal.
+    Response(TerminalResponse),
+    /// A well-formed escape sequence that isn't recognized as any known event. These are only
+    /// emitted by a [`Parser`] that's configured to
+    /// [report unknown sequences](Parser::report_unknown_sequences).
+    Unknown {
+        /// The raw bytes of the sequence.
+        raw: Vec<u8>,
     },
 }
+
+impl Event {
+    /// Returns the event that would be reported by a terminal using the legacy protocol. Key
+    /// events are mapped using [`KeyEvent::legacy_equivalent`], and other events are returned
+    /// unchanged.
+    ///
+    /// Returns [`None`] for key events that legacy terminals can't report.
+    pub fn legacy_equivalent(&self) -> Option<Self> {
+        match self {
+            Self::Key(key_event) => key_event.legacy_equivalent().map(Self::Key),
+            event => Some(event.clone()),
+        }
+    }
+}
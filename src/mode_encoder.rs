@@ -0,0 +1,468 @@
+use std::io::{self, Cursor, Write};
+
+use crate::encoder::{MAX_KITTY_KEY_LEN, write_paste};
+use crate::{
+    Encoding, Event, KeyEvent, KittyFlags, KittyFlagsMode, MouseEncoding, MouseEvent,
+    MouseEventKind, PasteSanitization, XtermOptions,
+};
+
+// Kitty doesn't specify a stack size, but it requires terminals to limit it to prevent DoS
+// attacks. Once the limit is reached, the oldest entries are discarded.
+const KITTY_STACK_LIMIT: usize = 16;
+// Longest CSI sequence we'll collect when scanning output. Anything longer isn't a sequence that
+// we're interested in.
+const MAX_CSI_LEN: usize = 64;
+
+/// Mouse tracking mode enabled by the application.
+#[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum MouseTracking {
+    /// Report button presses, releases, and scrolling (`CSI ? 1000 h`).
+    Normal,
+    /// Also report motion while a button is held down (`CSI ? 1002 h`).
+    ButtonEvent,
+    /// Also report all motion, even if no buttons are held down (`CSI ? 1003 h`).
+    AnyEvent,
+}
+
+/// A stateful encoder that tracks the terminal modes requested by an application and encodes
+/// events the way a terminal would while those modes are active.
+///
+/// This is useful for terminal emulators and multiplexers that forward [`Event`]s to a child
+/// process. Pass everything that the child writes to [`ModeEncoder::process_output`] so the
+/// encoder can keep track of the mode changes, then encode each event using
+/// [`ModeEncoder::encode`].
+///
+/// The following modes are tracked:
+///
+/// - Application cursor keys (DECCKM, `CSI ? 1 h`)
+/// - Application keypad (DECKPAM, `ESC =` or `CSI ? 66 h`)
+/// - Mouse tracking (`CSI ? 1000 h`, `CSI ? 1002 h`, and `CSI ? 1003 h`)
+/// - Mouse encoding (`CSI ? 1005 h`, `CSI ? 1006 h`, `CSI ? 1015 h`, and `CSI ? 1016 h`)
+/// - Focus reporting (`CSI ? 1004 h`)
+/// - Bracketed paste (`CSI ? 2004 h`)
+/// - In-band resize notifications (`CSI ? 2048 h`)
+/// - Xterm's modifyOtherKeys level (`CSI > 4 ; level m`)
+/// - The [Kitty keyboard protocol](https://sw.kovidgoyal.net/kitty/keyboard-protocol/#progressive-enhancement)
+///   flag stack. The main and alternate screens each have their own stack.
+///
+/// Events that the application hasn't asked for, such as mouse events when mouse tracking is
+/// disabled, are dropped and encode to zero bytes.
+///
+/// Pasted text is forwarded unchanged by default. Since the child can't tell where a paste ends
+/// if the text contains a paste terminator, a [`PasteSanitization`] should be set when the
+/// clipboard isn't trusted.
+///
+/// ```
+/// use terminput::{Event, KeyCode, KeyEvent, ModeEncoder};
+///
+/// let mut encoder = ModeEncoder::new();
+/// let up = Event::Key(KeyEvent::new(KeyCode::Up));
+/// assert_eq!(encoder.encode_to_vec(&up).unwrap(), b"\x1B[A");
+///
+/// // The application enabled application cursor keys
+/// encoder.process_output(b"\x1B[?1h");
+/// assert_eq!(encoder.encode_to_vec(&up).unwrap(), b"\x1BOA");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModeEncoder {
+    application_cursor_keys: bool,
+    application_keypad: bool,
+    mouse_tracking: Option<MouseTracking>,
+    mouse_encoding: MouseEncoding,
+    focus_events: bool,
+    bracketed_paste: bool,
+    in_band_resize: bool,
+    modify_other_keys: u8,
+    alternate_screen: bool,
+    // The first entry is always present and holds the flags used when nothing has been pushed
+    main_kitty_stack: Vec<KittyFlags>,
+    alternate_kitty_stack: Vec<KittyFlags>,
+    paste_sanitization: PasteSanitization,
+    state: ScanState,
+    sequence: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScanState {
+    Ground,
+    Escape,
+    EscapeIntermediate,
+    Csi,
+    String,
+    StringEscape,
+}
+
+impl Default for ModeEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ModeEncoder {
+    /// Creates a new [`ModeEncoder`] with all modes set to their defaults.
+    pub fn new() -> Self {
+        Self {
+            application_cursor_keys: false,
+            application_keypad: false,
+            mouse_tracking: None,
+            mouse_encoding: MouseEncoding::X10,
+            focus_events: false,
+            bracketed_paste: false,
+            in_band_resize: false,
+            modify_other_keys: 0,
+            alternate_screen: false,
+            main_kitty_stack: vec![KittyFlags::empty()],
+            alternate_kitty_stack: vec![KittyFlags::empty()],
+            paste_sanitization: PasteSanitization::Unchanged,
+            state: ScanState::Ground,
+            sequence: Vec::new(),
+        }
+    }
+
+    /// Sets how pasted text is sanitized before it's sent. This applies to every [`Encoding`] and
+    /// isn't affected by [`ModeEncoder::reset`].
+    pub fn paste_sanitization(mut self, paste_sanitization: PasteSanitization) -> Self {
+        self.paste_sanitization = paste_sanitization;
+        self
+    }
+
+    /// Resets all modes to their defaults, as if the terminal received a full reset (`ESC c`).
+    pub fn reset(&mut self) {
+        *self = Self::new().paste_sanitization(self.paste_sanitization);
+    }
+
+    /// Whether application cursor keys (DECCKM) are enabled.
+    pub fn application_cursor_keys(&self) -> bool {
+        self.application_cursor_keys
+    }
+
+    /// Whether the application keypad (DECKPAM) is enabled.
+    pub fn application_keypad(&self) -> bool {
+        self.application_keypad
+    }
+
+    /// The active mouse tracking mode, if any.
+    pub fn mouse_tracking(&self) -> Option<MouseTracking> {
+        self.mouse_tracking
+    }
+
+    /// The encoding used for mouse events.
+    pub fn mouse_encoding(&self) -> MouseEncoding {
+        self.mouse_encoding
+    }
+
+    /// Whether focus events are reported.
+    pub fn focus_events(&self) -> bool {
+        self.focus_events
+    }
+
+    /// Whether bracketed paste is enabled.
+    pub fn bracketed_paste(&self) -> bool {
+        self.bracketed_paste
+    }
+
+    /// Whether in-band resize notifications are enabled.
+    pub fn in_band_resize(&self) -> bool {
+        self.in_band_resize
+    }
+
+    /// The active modifyOtherKeys level. `0` means the setting is disabled.
+    pub fn modify_other_keys(&self) -> u8 {
+        self.modify_other_keys
+    }
+
+    /// The Kitty keyboard flags that are active on the current screen.
+    pub fn kitty_flags(&self) -> KittyFlags {
+        self.kitty_stack()
+            .last()
+            .copied()
+            .unwrap_or_else(KittyFlags::empty)
+    }
+
+    /// The [`XtermOptions`] that correspond to the active modes.
+    pub fn xterm_options(&self) -> XtermOptions {
+        XtermOptions::new()
+            .application_cursor_keys(self.application_cursor_keys)
+            .application_keypad(self.application_keypad)
+            .mouse_encoding(self.mouse_encoding)
+    }
+
+    /// The [`Encoding`] used for key events, based on the active modes and keyboard protocols.
+    /// The Kitty keyboard protocol takes precedence over modifyOtherKeys.
+    pub fn encoding(&self) -> Encoding {
+        let flags = self.kitty_flags();
+        if !flags.is_empty() {
+            Encoding::Kitty(flags)
+        } else if self.modify_other_keys > 0 {
+            Encoding::ModifyOtherKeys(self.modify_other_keys)
+        } else {
+            Encoding::XtermWith(self.xterm_options())
+        }
+    }
+
+    /// Scans the output written by the application for mode changes. The output doesn't need to
+    /// be split on sequence boundaries; partial sequences are remembered until the next call.
+    pub fn process_output(&mut self, output: &[u8]) {
+        for &byte in output {
+            self.advance(byte);
+        }
+    }
+
+    /// Encode the event into the given buffer based on the active modes. Returns `0` if the
+    /// event was dropped.
+    ///
+    /// Returns an error if the buffer is too small to hold the encoded event.
+    pub fn encode(&self, event: &Event, buf: &mut [u8]) -> io::Result<usize> {
+        let mut buf = Cursor::new(buf);
+        self.encode_to_writer(event, &mut buf)
+    }
+
+    /// Encode the event into a new [`Vec`] based on the active modes. Returns an empty [`Vec`]
+    /// if the event was dropped.
+    pub fn encode_to_vec(&self, event: &Event) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        self.encode_to_writer(event, &mut buf)?;
+        Ok(buf)
+    }
+
+    /// Encode the event into the given writer based on the active modes. Returns the number of
+    /// bytes written, which is `0` if the event was dropped.
+    pub fn encode_to_writer<W>(&self, event: &Event, writer: &mut W) -> io::Result<usize>
+    where
+        W: Write,
+    {
+        match event {
+            Event::FocusGained | Event::FocusLost if !self.focus_events => Ok(0),
+            Event::Resize { .. } if !self.in_band_resize => Ok(0),
+            Event::Paste(text) if !self.bracketed_paste => {
+                // Without bracketed paste, the text is sent as if it was typed. Terminals send
+                // carriage returns for newlines, the same as the Enter key.
+                let text = self.paste_sanitization.sanitize(text);
+                let text = text.replace("\r\n", "\r").replace('\n', "\r");
+                writer.write_all(text.as_bytes())?;
+                Ok(text.len())
+            }
+            Event::Paste(text) => write_paste(text, self.paste_sanitization, writer),
+            Event::Key(key_event) => self.encode_key(key_event, writer),
+            Event::Mouse(mouse_event) => self.encode_mouse(mouse_event, writer),
+            _ => event.encode_to_writer(writer, self.encoding()),
+        }
+    }
+
+    fn encode_key<W>(&self, key_event: &KeyEvent, writer: &mut W) -> io::Result<usize>
+    where
+        W: Write,
+    {
+        let event = Event::Key(*key_event);
+        // Keys that fall back to the legacy encoding still respect the Xterm modes
+        let options = self.xterm_options();
+        let mut buf = [0; MAX_KITTY_KEY_LEN];
+        let written = match self.encoding() {
+            Encoding::Kitty(flags) => event.to_kitty_escape_sequence(&mut buf, flags, options)?,
+            Encoding::ModifyOtherKeys(level) => {
+                event.to_modify_other_keys_escape_sequence(&mut buf, level, options)?
+            }
+            encoding => event.encode(&mut buf, encoding)?,
+        };
+        writer.write_all(&buf[..written])?;
+        Ok(written)
+    }
+
+    fn encode_mouse<W>(&self, mouse_event: &MouseEvent, writer: &mut W) -> io::Result<usize>
+    where
+        W: Write,
+    {
+        let reported = match self.mouse_tracking {
+            None => false,
+            Some(MouseTracking::Normal) => !matches!(
+                mouse_event.kind,
+                MouseEventKind::Drag(_) | MouseEventKind::Moved
+            ),
+            Some(MouseTracking::ButtonEvent) => mouse_event.kind != MouseEventKind::Moved,
+            Some(MouseTracking::AnyEvent) => true,
+        };
+        if !reported {
+            return Ok(0);
+        }
+        Event::Mouse(*mouse_event)
+            .encode_to_writer(writer, Encoding::XtermWith(self.xterm_options()))
+    }
+
+    fn kitty_stack(&self) -> &[KittyFlags] {
+        if self.alternate_screen {
+            &self.alternate_kitty_stack
+        } else {
+            &self.main_kitty_stack
+        }
+    }
+
+    fn kitty_stack_mut(&mut self) -> &mut Vec<KittyFlags> {
+        if self.alternate_screen {
+            &mut self.alternate_kitty_stack
+        } else {
+            &mut self.main_kitty_stack
+        }
+    }
+
+    fn advance(&mut self, byte: u8) {
+        match (self.state, byte) {
+            // CAN and SUB cancel any sequence in progress
+            (_, b'\x18' | b'\x1A') => self.state = ScanState::Ground,
+            (ScanState::String, b'\x07') => self.state = ScanState::Ground,
+            (ScanState::String, b'\x1B') => self.state = ScanState::StringEscape,
+            (ScanState::String, _) => {}
+            (ScanState::StringEscape, b'\\') => self.state = ScanState::Ground,
+            (_, b'\x1B') => self.state = ScanState::Escape,
+            (ScanState::Ground, _) => {}
+            (ScanState::Escape | ScanState::StringEscape, b'[') => {
+                self.sequence.clear();
+                self.state = ScanState::Csi;
+            }
+            (ScanState::Escape | ScanState::StringEscape, b']' | b'P' | b'X' | b'^' | b'_') => {
+                self.state = ScanState::String;
+            }
+            (ScanState::Escape | ScanState::StringEscape, b' '..=b'/') => {
+                self.state = ScanState::EscapeIntermediate;
+            }
+            (ScanState::Escape | ScanState::StringEscape, b'=') => {
+                self.application_keypad = true;
+                self.state = ScanState::Ground;
+            }
+            (ScanState::Escape | ScanState::StringEscape, b'>') => {
+                self.application_keypad = false;
+                self.state = ScanState::Ground;
+            }
+            (ScanState::Escape | ScanState::StringEscape, b'c') => self.reset(),
+            (ScanState::EscapeIntermediate, b' '..=b'/') => {}
+            (ScanState::Escape | ScanState::StringEscape | ScanState::EscapeIntermediate, _) => {
+                self.state = ScanState::Ground;
+            }
+            (ScanState::Csi, b'@'..=b'~') => {
+                self.state = ScanState::Ground;
+                let sequence = std::mem::take(&mut self.sequence);
+                self.dispatch_csi(&sequence, byte);
+                self.sequence = sequence;
+            }
+            (ScanState::Csi, b' '..=b'?') => {
+                if self.sequence.len() < MAX_CSI_LEN {
+                    self.sequence.push(byte);
+                } else {
+                    self.state = ScanState::Ground;
+                }
+            }
+            // Other control characters are executed without interrupting the sequence
+            (ScanState::Csi, _) => {}
+        }
+    }
+
+    fn dispatch_csi(&mut self, sequence: &[u8], final_byte: u8) {
+        let Ok(sequence) = std::str::from_utf8(sequence) else {
+            return;
+        };
+        match (sequence.chars().next(), final_byte) {
+            (Some('?'), b'h' | b'l') => {
+                for mode in sequence[1..].split(';') {
+                    if let Ok(mode) = mode.parse() {
+                        self.set_mode(mode, final_byte == b'h');
+                    }
+                }
+            }
+            // XTMODKEYS. Only the modifyOtherKeys resource (4) is tracked. Omitting the value
+            // or the resource resets it.
+            (Some('>'), b'm') => {
+                let mut params = sequence[1..].split(';');
+                if let Some("" | "4") = params.next() {
+                    self.modify_other_keys =
+                        params.next().and_then(|p| p.parse().ok()).unwrap_or(0);
+                }
+            }
+            (Some('>'), b'n') if &sequence[1..] == "4" => self.modify_other_keys = 0,
+            (Some('>'), b'u') => {
+                let flags = parse_flags(&sequence[1..]);
+                let stack = self.kitty_stack_mut();
+                if stack.len() > KITTY_STACK_LIMIT {
+                    stack.remove(1);
+                }
+                stack.push(flags);
+            }
+            (Some('<'), b'u') => {
+                let count = match &sequence[1..] {
+                    "" => 1,
+                    count => count.parse().unwrap_or(1).max(1),
+                };
+                let stack = self.kitty_stack_mut();
+                if count >= stack.len() {
+                    // Popping every entry resets the flags
+                    *stack = vec![KittyFlags::empty()];
+                } else {
+                    stack.truncate(stack.len() - count);
+                }
+            }
+            (Some('='), b'u') => {
+                let (flags, mode) = sequence[1..]
+                    .split_once(';')
+                    .unwrap_or((&sequence[1..], ""));
+                let flags = parse_flags(flags);
+                let mode = match mode {
+                    "2" => KittyFlagsMode::Enable,
+                    "3" => KittyFlagsMode::Disable,
+                    _ => KittyFlagsMode::Replace,
+                };
+                let stack = self.kitty_stack_mut();
+                if let Some(current) = stack.last_mut() {
+                    match mode {
+                        KittyFlagsMode::Replace => *current = flags,
+                        KittyFlagsMode::Enable => *current |= flags,
+                        KittyFlagsMode::Disable => *current -= flags,
+                    }
+                }
+            }
+            // Soft terminal reset (DECSTR)
+            (Some('!'), b'p') if sequence == "!" => {
+                self.application_cursor_keys = false;
+                self.application_keypad = false;
+            }
+            _ => {}
+        }
+    }
+
+    fn set_mode(&mut self, mode: u16, enabled: bool) {
+        match mode {
+            1 => self.application_cursor_keys = enabled,
+            66 => self.application_keypad = enabled,
+            // Disabling any of the tracking modes turns off mouse reporting
+            1000 => self.set_mouse_tracking(MouseTracking::Normal, enabled),
+            1002 => self.set_mouse_tracking(MouseTracking::ButtonEvent, enabled),
+            1003 => self.set_mouse_tracking(MouseTracking::AnyEvent, enabled),
+            1004 => self.focus_events = enabled,
+            1005 => self.set_mouse_encoding(MouseEncoding::Utf8, enabled),
+            1006 => self.set_mouse_encoding(MouseEncoding::Sgr, enabled),
+            1015 => self.set_mouse_encoding(MouseEncoding::Urxvt, enabled),
+            1016 => self.set_mouse_encoding(MouseEncoding::SgrPixels, enabled),
+            47 | 1047 | 1049 => self.alternate_screen = enabled,
+            2004 => self.bracketed_paste = enabled,
+            2048 => self.in_band_resize = enabled,
+            _ => {}
+        }
+    }
+
+    fn set_mouse_encoding(&mut self, mouse_encoding: MouseEncoding, enabled: bool) {
+        // The most recently enabled encoding is used. Disabling it reverts to X10 encoding.
+        if enabled {
+            self.mouse_encoding = mouse_encoding;
+        } else if self.mouse_encoding == mouse_encoding {
+            self.mouse_encoding = MouseEncoding::X10;
+        }
+    }
+
+    fn set_mouse_tracking(&mut self, mouse_tracking: MouseTracking, enabled: bool) {
+        self.mouse_tracking = enabled.then_some(mouse_tracking);
+    }
+}
+
+fn parse_flags(flags: &str) -> KittyFlags {
+    flags
+        .parse::<u8>()
+        .map(KittyFlags::from)
+        .unwrap_or(KittyFlags::empty())
+}
@@ -11,6 +11,21 @@ pub struct MouseEvent {
     pub row: u16,
     /// The key modifiers active when the event occurred.
     pub modifiers: KeyModifiers,
+    /// The position of the event in pixels, if it was reported by the input source. This is
+    /// more precise than the column and row.
+    ///
+    /// Some sources, such as the [SGR-Pixels](MouseEncoding::SgrPixels) encoding, only report the
+    /// pixel position. In that case, the column and row are set to `0`.
+    pub pixel_position: Option<PixelPosition>,
+}
+
+/// The position of a mouse event in pixels. The upper left corner is `(0, 0)`.
+#[derive(Debug, PartialOrd, Ord, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct PixelPosition {
+    /// The horizontal position.
+    pub x: u16,
+    /// The vertical position.
+    pub y: u16,
 }
 
 /// The type of mouse event.
@@ -43,6 +58,48 @@ pub enum MouseButton {
     Right,
     /// Middle mouse button.
     Middle,
+    /// Back button, which is usually used to navigate backwards. This is button 8 in X11.
+    Back,
+    /// Forward button, which is usually used to navigate forwards. This is button 9 in X11.
+    Forward,
+    /// Any other button, identified by its X11 button number. Xterm-compatible terminals can
+    /// report buttons up to 11.
+    Other(u8),
     /// Mouse button could not be determined.
     Unknown,
 }
+
+/// Protocol used to report mouse events. Applications select the encoding by enabling the
+/// corresponding mode, such as `CSI ? 1006 h`. If none of these modes are enabled, terminals use
+/// the X10 encoding.
+///
+/// See Xterm's documentation on
+/// [extended coordinates](https://invisible-island.net/xterm/ctlseqs/ctlseqs.html#h2-Extended-coordinates)
+/// for more details.
+#[derive(Debug, Default, PartialOrd, Ord, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum MouseEncoding {
+    /// The original encoding, `CSI M Cb Cx Cy`. Each value is offset by 32 and sent as a single
+    /// byte, so positions past column or row 223 can't be represented. Releases don't report
+    /// which button was released.
+    X10,
+    /// UTF-8 extended encoding (mode 1005). This is the same as [`MouseEncoding::X10`], but each
+    /// value is sent as a UTF-8 encoded character, which extends the limit to column or row 2015.
+    ///
+    /// These sequences can't be distinguished from X10 sequences that contain large
+    /// coordinates, so the [`Parser`](crate::Parser) needs to be
+    /// [configured](crate::Parser::mouse_encoding) to use this encoding.
+    Utf8,
+    /// Urxvt encoding (mode 1015), `CSI Cb ; Cx ; Cy M`. The values are sent as decimal numbers
+    /// and `Cb` is offset by 32. Releases don't report which button was released.
+    Urxvt,
+    /// SGR encoding (mode 1006), `CSI < Cb ; Cx ; Cy M`. Releases end in `m` instead of `M`.
+    #[default]
+    Sgr,
+    /// SGR-Pixels encoding (mode 1016). This is the same as [`MouseEncoding::Sgr`], but the
+    /// [pixel position](MouseEvent::pixel_position) is reported instead of the column and row.
+    ///
+    /// These sequences can't be distinguished from SGR sequences, so the
+    /// [`Parser`](crate::Parser) needs to be [configured](crate::Parser::mouse_encoding) to use
+    /// this encoding.
+    SgrPixels,
+}
@@ -0,0 +1,322 @@
+use super::{NotationError, find_name, lookup_value};
+use crate::{KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers, MediaKeyCode};
+
+// Keys that are written without angle brackets. The first name for each key is the one used for
+// formatting.
+const EMACS_KEY_NAMES: [(KeyCode, &str); 5] = [
+    (KeyCode::Enter, "RET"),
+    (KeyCode::Tab, "TAB"),
+    (KeyCode::Esc, "ESC"),
+    (KeyCode::Char(' '), "SPC"),
+    (KeyCode::Backspace, "DEL"),
+];
+
+// Keys that are written in angle brackets, such as `<left>`
+const EMACS_FUNCTION_KEY_NAMES: [(KeyCode, &str); 20] = [
+    (KeyCode::Delete, "delete"),
+    (KeyCode::Insert, "insert"),
+    (KeyCode::Home, "home"),
+    (KeyCode::End, "end"),
+    (KeyCode::PageUp, "prior"),
+    (KeyCode::PageDown, "next"),
+    (KeyCode::Up, "up"),
+    (KeyCode::Down, "down"),
+    (KeyCode::Left, "left"),
+    (KeyCode::Right, "right"),
+    (KeyCode::KeypadBegin, "begin"),
+    (KeyCode::PrintScreen, "print"),
+    (KeyCode::Pause, "pause"),
+    (KeyCode::Menu, "menu"),
+    (KeyCode::ScrollLock, "scroll"),
+    (KeyCode::Enter, "return"),
+    (KeyCode::Tab, "tab"),
+    (KeyCode::Esc, "escape"),
+    (KeyCode::Backspace, "backspace"),
+    (KeyCode::Delete, "deletechar"),
+];
+
+// Keys with the `KEYPAD` state
+const EMACS_KEYPAD_NAMES: [(KeyCode, &str); 28] = [
+    (KeyCode::Char('0'), "kp-0"),
+    (KeyCode::Char('1'), "kp-1"),
+    (KeyCode::Char('2'), "kp-2"),
+    (KeyCode::Char('3'), "kp-3"),
+    (KeyCode::Char('4'), "kp-4"),
+    (KeyCode::Char('5'), "kp-5"),
+    (KeyCode::Char('6'), "kp-6"),
+    (KeyCode::Char('7'), "kp-7"),
+    (KeyCode::Char('8'), "kp-8"),
+    (KeyCode::Char('9'), "kp-9"),
+    (KeyCode::Char('+'), "kp-add"),
+    (KeyCode::Char('-'), "kp-subtract"),
+    (KeyCode::Char('*'), "kp-multiply"),
+    (KeyCode::Char('/'), "kp-divide"),
+    (KeyCode::Char('.'), "kp-decimal"),
+    (KeyCode::Char(','), "kp-separator"),
+    (KeyCode::Char('='), "kp-equal"),
+    (KeyCode::Enter, "kp-enter"),
+    (KeyCode::Up, "kp-up"),
+    (KeyCode::Down, "kp-down"),
+    (KeyCode::Left, "kp-left"),
+    (KeyCode::Right, "kp-right"),
+    (KeyCode::Home, "kp-home"),
+    (KeyCode::End, "kp-end"),
+    (KeyCode::PageUp, "kp-prior"),
+    (KeyCode::PageDown, "kp-next"),
+    (KeyCode::Insert, "kp-insert"),
+    (KeyCode::Delete, "kp-delete"),
+];
+
+const EMACS_MEDIA_KEY_NAMES: [(MediaKeyCode, &str); 11] = [
+    (MediaKeyCode::Play, "XF86AudioPlay"),
+    (MediaKeyCode::Pause, "XF86AudioPause"),
+    (MediaKeyCode::Stop, "XF86AudioStop"),
+    (MediaKeyCode::TrackNext, "XF86AudioNext"),
+    (MediaKeyCode::TrackPrevious, "XF86AudioPrev"),
+    (MediaKeyCode::Record, "XF86AudioRecord"),
+    (MediaKeyCode::Rewind, "XF86AudioRewind"),
+    (MediaKeyCode::FastForward, "XF86AudioForward"),
+    (MediaKeyCode::LowerVolume, "XF86AudioLowerVolume"),
+    (MediaKeyCode::RaiseVolume, "XF86AudioRaiseVolume"),
+    (MediaKeyCode::MuteVolume, "XF86AudioMute"),
+];
+
+/// Converts between key sequences and the Emacs key notation used by `kbd` and
+/// `key-description`, such as `C-x C-f` or `M-<return>`.
+///
+/// Keys in a sequence are separated by whitespace, and each key can be combined with the `C-`
+/// (control), `M-` (meta), `S-` (shift), `s-` (super), `H-` (hyper), and `A-` (alt) modifiers.
+/// Function keys are written in angle brackets, such as `<left>` or `<f1>`, and keys on the keypad
+/// use the `<kp-...>` names, such as `<kp-5>`, which map to [`KeyEventState::KEYPAD`]. `RET`,
+/// `TAB`, `ESC`, `SPC`, and `DEL` are used for Enter, Tab, Escape, Space, and Backspace. A word
+/// without modifiers that isn't a key name is treated as a sequence of characters, so `C-x ab` is
+/// three keys. Control combined with a letter is case-insensitive unless `S-` is given, so `C-A`
+/// is the same as `C-a`, and Ctrl+Shift+A is written as `C-S-a`.
+///
+/// Emacs's Meta modifier is sent by the Alt key in most terminals, so by default, `M-` refers to
+/// [`KeyModifiers::ALT`]. See [`EmacsNotation::alt_as_meta`] to keep Meta and Alt separate.
+///
+/// ```
+/// use terminput::{EmacsNotation, KeyCode, KeyEvent, KeyModifiers};
+///
+/// let notation = EmacsNotation::new();
+/// let keys = notation.parse("C-x C-f M-<return>").unwrap();
+/// assert_eq!(
+///     keys,
+///     vec![
+///         KeyEvent::new(KeyCode::Char('x')).modifiers(KeyModifiers::CTRL),
+///         KeyEvent::new(KeyCode::Char('f')).modifiers(KeyModifiers::CTRL),
+///         KeyEvent::new(KeyCode::Enter).modifiers(KeyModifiers::ALT),
+///     ]
+/// );
+/// assert_eq!(notation.format(&keys).unwrap(), "C-x C-f M-RET");
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct EmacsNotation {
+    alt_as_meta: bool,
+}
+
+impl Default for EmacsNotation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EmacsNotation {
+    /// Creates a new [`EmacsNotation`] that treats the Alt key as Meta.
+    pub const fn new() -> Self {
+        Self { alt_as_meta: true }
+    }
+
+    /// Sets whether the Alt key is treated as Emacs's Meta modifier. This is enabled by default,
+    /// since terminals send Meta using the Alt key.
+    ///
+    /// When enabled, both `M-` and `A-` are parsed as [`KeyModifiers::ALT`], and
+    /// [`KeyModifiers::ALT`] is formatted as `M-`. Keys with [`KeyModifiers::META`] can't be
+    /// formatted, since they would be parsed back as Alt. When disabled, `M-` refers to
+    /// [`KeyModifiers::META`] and `A-` refers to [`KeyModifiers::ALT`], the same as in a graphical
+    /// Emacs.
+    pub const fn alt_as_meta(mut self, alt_as_meta: bool) -> Self {
+        self.alt_as_meta = alt_as_meta;
+        self
+    }
+
+    /// Parses a sequence of keys separated by whitespace, such as `C-x C-f`.
+    ///
+    /// Returns an error if the sequence is empty or contains an unknown key name.
+    pub fn parse(&self, notation: &str) -> Result<Vec<KeyEvent>, NotationError> {
+        let mut keys = Vec::new();
+        for word in notation.split_whitespace() {
+            self.parse_word(word, &mut keys)?;
+        }
+        if keys.is_empty() {
+            return Err(NotationError::MissingKey {
+                input: notation.to_string(),
+            });
+        }
+        Ok(keys)
+    }
+
+    /// Formats a sequence of keys separated by spaces, such as `C-x C-f`.
+    ///
+    /// Returns an error if any of the keys can't be represented in Emacs notation, such as
+    /// modifier keys or key releases.
+    pub fn format(&self, keys: &[KeyEvent]) -> Result<String, NotationError> {
+        let keys = keys
+            .iter()
+            .map(|key| self.format_key(key))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(keys.join(" "))
+    }
+
+    /// Formats a single key in Emacs notation, such as `C-M-%` or `s-<left>`. Caps Lock and Num
+    /// Lock states are ignored.
+    ///
+    /// Returns an error if the key can't be represented in Emacs notation.
+    pub fn format_key(&self, key_event: &KeyEvent) -> Result<String, NotationError> {
+        let unsupported = || NotationError::UnsupportedKey {
+            key: key_event.to_string(),
+        };
+        let key_event = key_event.normalize_case();
+        let mut modifiers = key_event.modifiers;
+        // Meta can't be told apart from Alt when they share a prefix
+        if key_event.kind != KeyEventKind::Press
+            || (self.alt_as_meta && modifiers.contains(KeyModifiers::META))
+        {
+            return Err(unsupported());
+        }
+        let keypad_name = if key_event.state.contains(KeyEventState::KEYPAD) {
+            find_name(&EMACS_KEYPAD_NAMES, &key_event.code)
+        } else {
+            None
+        };
+        let name = match (keypad_name, key_event.code) {
+            (Some(name), _) => format!("<{name}>"),
+            (None, KeyCode::Char(c)) if c.is_control() => return Err(unsupported()),
+            (None, KeyCode::Char(c)) => {
+                let mut c = c;
+                if c.is_ascii_uppercase() && modifiers.contains(KeyModifiers::CTRL) {
+                    // C-A means the same thing as C-a, so shift needs to be explicit
+                    c = c.to_ascii_lowercase();
+                } else if c.is_ascii_uppercase() {
+                    // Uppercase letters already include shift
+                    modifiers.remove(KeyModifiers::SHIFT);
+                }
+                find_name(&EMACS_KEY_NAMES, &KeyCode::Char(c))
+                    .map(ToString::to_string)
+                    .unwrap_or_else(|| c.to_string())
+            }
+            (None, KeyCode::F(n)) => format!("<f{n}>"),
+            (None, KeyCode::Media(media)) => {
+                format!(
+                    "<{}>",
+                    find_name(&EMACS_MEDIA_KEY_NAMES, &media).ok_or_else(unsupported)?
+                )
+            }
+            (None, code) => {
+                if let Some(name) = find_name(&EMACS_KEY_NAMES, &code) {
+                    name.to_string()
+                } else {
+                    format!(
+                        "<{}>",
+                        find_name(&EMACS_FUNCTION_KEY_NAMES, &code).ok_or_else(unsupported)?
+                    )
+                }
+            }
+        };
+
+        let mut formatted = String::new();
+        for (modifier, prefix) in self.modifiers() {
+            if modifiers.intersects(modifier) {
+                formatted.push_str(prefix);
+            }
+        }
+        formatted.push_str(&name);
+        Ok(formatted)
+    }
+
+    // Modifiers in the order that Emacs writes them
+    const fn modifiers(&self) -> [(KeyModifiers, &'static str); 6] {
+        let (alt, meta) = if self.alt_as_meta {
+            (KeyModifiers::NONE, KeyModifiers::ALT)
+        } else {
+            (KeyModifiers::ALT, KeyModifiers::META)
+        };
+        [
+            (alt, "A-"),
+            (KeyModifiers::CTRL, "C-"),
+            (KeyModifiers::HYPER, "H-"),
+            (meta, "M-"),
+            (KeyModifiers::SHIFT, "S-"),
+            (KeyModifiers::SUPER, "s-"),
+        ]
+    }
+
+    fn parse_word(&self, word: &str, keys: &mut Vec<KeyEvent>) -> Result<(), NotationError> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut name = word;
+        while let [prefix, b'-', _, ..] = name.as_bytes() {
+            modifiers |= match prefix {
+                b'A' => KeyModifiers::ALT,
+                b'M' if self.alt_as_meta => KeyModifiers::ALT,
+                b'M' => KeyModifiers::META,
+                b'C' => KeyModifiers::CTRL,
+                b'H' => KeyModifiers::HYPER,
+                b'S' => KeyModifiers::SHIFT,
+                b's' => KeyModifiers::SUPER,
+                _ => break,
+            };
+            name = &name[2..];
+        }
+
+        let mut state = KeyEventState::NONE;
+        let mut chars = name.chars();
+        let code = if let (Some(c), None) = (chars.next(), chars.next()) {
+            // Control is case-insensitive unless shift is given explicitly
+            if modifiers.contains(KeyModifiers::CTRL) && !modifiers.contains(KeyModifiers::SHIFT) {
+                KeyCode::Char(c.to_ascii_lowercase())
+            } else {
+                KeyCode::Char(c)
+            }
+        } else if let Some(code) = lookup_value(&EMACS_KEY_NAMES, name) {
+            code
+        } else if let Some(function_key) = name
+            .strip_prefix('<')
+            .and_then(|name| name.strip_suffix('>'))
+            .filter(|name| !name.is_empty())
+        {
+            if let Some(code) = lookup_value(&EMACS_KEYPAD_NAMES, function_key) {
+                state = KeyEventState::KEYPAD;
+                code
+            } else if let Some(code) = lookup_value(&EMACS_FUNCTION_KEY_NAMES, function_key) {
+                code
+            } else if let Some(media) = lookup_value(&EMACS_MEDIA_KEY_NAMES, function_key) {
+                KeyCode::Media(media)
+            } else if let Some(Ok(n)) = function_key.strip_prefix('f').map(str::parse) {
+                KeyCode::F(n)
+            } else {
+                return Err(NotationError::UnknownKey {
+                    name: name.to_string(),
+                });
+            }
+        } else if modifiers.is_empty() {
+            // A word of plain characters
+            keys.extend(
+                name.chars()
+                    .map(|c| KeyEvent::new(KeyCode::Char(c)).normalize_case()),
+            );
+            return Ok(());
+        } else {
+            return Err(NotationError::UnknownKey {
+                name: name.to_string(),
+            });
+        };
+        keys.push(
+            KeyEvent::new(code)
+                .modifiers(modifiers)
+                .state(state)
+                .normalize_case(),
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+use std::error::Error;
+use std::fmt;
+
+/// An error that occurred while parsing a key from its text notation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum NotationError {
+    /// The input didn't contain a key.
+    MissingKey {
+        /// The input that was parsed.
+        input: String,
+    },
+    /// The key name isn't recognized.
+    UnknownKey {
+        /// The unrecognized key name.
+        name: String,
+    },
+    /// The modifier name isn't recognized.
+    UnknownModifier {
+        /// The unrecognized modifier name.
+        name: String,
+    },
+    /// The same modifier was given more than once.
+    DuplicateModifier {
+        /// The repeated modifier name.
+        name: String,
+    },
+    /// The key can't be represented in the requested notation.
+    UnsupportedKey {
+        /// The key in the canonical notation used by [`KeyEvent`](crate::KeyEvent)'s
+        /// [`Display`](std::fmt::Display) implementation.
+        key: String,
+    },
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingKey { input } => write!(f, "Missing key in {input:?}"),
+            Self::UnknownKey { name } => write!(f, "Unknown key name {name:?}"),
+            Self::UnknownModifier { name } => write!(f, "Unknown modifier {name:?}"),
+            Self::DuplicateModifier { name } => write!(f, "Modifier {name:?} was given twice"),
+            Self::UnsupportedKey { key } => {
+                write!(f, "Key {key:?} can't be represented in this notation")
+            }
+        }
+    }
+}
+
+impl Error for NotationError {}
@@ -0,0 +1,373 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{
+    KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers, MediaKeyCode, ModifierDirection,
+    ModifierKeyCode,
+};
+
+mod emacs;
+mod error;
+mod tmux;
+mod vim;
+
+pub use emacs::*;
+pub use error::*;
+pub use tmux::*;
+pub use vim::*;
+
+const MODIFIER_NAMES: [(KeyModifiers, &str); 6] = [
+    (KeyModifiers::CTRL, "ctrl"),
+    (KeyModifiers::ALT, "alt"),
+    (KeyModifiers::SHIFT, "shift"),
+    (KeyModifiers::SUPER, "super"),
+    (KeyModifiers::HYPER, "hyper"),
+    (KeyModifiers::META, "meta"),
+];
+
+const STATE_NAMES: [(KeyEventState, &str); 3] = [
+    (KeyEventState::CAPS_LOCK, "capslock"),
+    (KeyEventState::NUM_LOCK, "numlock"),
+    (KeyEventState::KEYPAD, "keypad"),
+];
+
+const KEY_NAMES: [(KeyCode, &str); 21] = [
+    (KeyCode::Backspace, "backspace"),
+    (KeyCode::Enter, "enter"),
+    (KeyCode::Left, "left"),
+    (KeyCode::Right, "right"),
+    (KeyCode::Up, "up"),
+    (KeyCode::Down, "down"),
+    (KeyCode::Home, "home"),
+    (KeyCode::End, "end"),
+    (KeyCode::PageUp, "pageup"),
+    (KeyCode::PageDown, "pagedown"),
+    (KeyCode::Tab, "tab"),
+    (KeyCode::Delete, "delete"),
+    (KeyCode::Insert, "insert"),
+    (KeyCode::Esc, "esc"),
+    (KeyCode::CapsLock, "capslock"),
+    (KeyCode::ScrollLock, "scrolllock"),
+    (KeyCode::NumLock, "numlock"),
+    (KeyCode::PrintScreen, "printscreen"),
+    (KeyCode::Pause, "pause"),
+    (KeyCode::Menu, "menu"),
+    (KeyCode::KeypadBegin, "begin"),
+];
+
+// Alternate spellings that are accepted when parsing
+const KEY_ALIASES: [(KeyCode, &str); 9] = [
+    (KeyCode::Enter, "return"),
+    (KeyCode::Esc, "escape"),
+    (KeyCode::Delete, "del"),
+    (KeyCode::Insert, "ins"),
+    (KeyCode::PageUp, "pgup"),
+    (KeyCode::PageDown, "pgdn"),
+    (KeyCode::Char(' '), "space"),
+    (KeyCode::Char('+'), "plus"),
+    (KeyCode::Char('-'), "minus"),
+];
+
+const MEDIA_KEY_NAMES: [(MediaKeyCode, &str); 13] = [
+    (MediaKeyCode::Play, "mediaplay"),
+    (MediaKeyCode::Pause, "mediapause"),
+    (MediaKeyCode::PlayPause, "mediaplaypause"),
+    (MediaKeyCode::Reverse, "mediareverse"),
+    (MediaKeyCode::Stop, "mediastop"),
+    (MediaKeyCode::FastForward, "mediafastforward"),
+    (MediaKeyCode::Rewind, "mediarewind"),
+    (MediaKeyCode::TrackNext, "mediatracknext"),
+    (MediaKeyCode::TrackPrevious, "mediatrackprevious"),
+    (MediaKeyCode::Record, "mediarecord"),
+    (MediaKeyCode::LowerVolume, "volumedown"),
+    (MediaKeyCode::RaiseVolume, "volumeup"),
+    (MediaKeyCode::MuteVolume, "volumemute"),
+];
+
+const MODIFIER_KEY_NAMES: [(ModifierKeyCode, &str); 8] = [
+    (ModifierKeyCode::Shift, "shift"),
+    (ModifierKeyCode::Control, "ctrl"),
+    (ModifierKeyCode::Alt, "alt"),
+    (ModifierKeyCode::Super, "super"),
+    (ModifierKeyCode::Hyper, "hyper"),
+    (ModifierKeyCode::Meta, "meta"),
+    (ModifierKeyCode::IsoLevel3Shift, "isolevel3shift"),
+    (ModifierKeyCode::IsoLevel5Shift, "isolevel5shift"),
+];
+
+/// Formats the key in the canonical notation used by [`KeyEvent`]'s [`Display`](fmt::Display)
+/// implementation, such as `enter`, `f12`, `a`, or `leftshift`.
+impl fmt::Display for KeyCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Char(' ') => f.write_str("space"),
+            Self::Char('+') => f.write_str("plus"),
+            Self::Char(c) => write!(f, "{c}"),
+            Self::F(n) => write!(f, "f{n}"),
+            Self::Media(media) => f.write_str(lookup_name(&MEDIA_KEY_NAMES, media)),
+            Self::Modifier(modifier, direction) => {
+                match direction {
+                    ModifierDirection::Left => f.write_str("left")?,
+                    ModifierDirection::Right => f.write_str("right")?,
+                    ModifierDirection::Unknown => {}
+                }
+                f.write_str(lookup_name(&MODIFIER_KEY_NAMES, modifier))
+            }
+            _ => f.write_str(lookup_name(&KEY_NAMES, self)),
+        }
+    }
+}
+
+/// Parses a key name in the canonical notation. Names are case-insensitive, except for single
+/// characters.
+impl FromStr for KeyCode {
+    type Err = NotationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut chars = s.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Ok(Self::Char(c));
+        }
+        let name = s.to_ascii_lowercase();
+        if let Some(n) = name.strip_prefix('f').and_then(|n| n.parse().ok()) {
+            return Ok(Self::F(n));
+        }
+        if let Some(code) = lookup_value(&KEY_NAMES, &name) {
+            return Ok(code);
+        }
+        if let Some(code) = lookup_value(&KEY_ALIASES, &name) {
+            return Ok(code);
+        }
+        if let Some(media) = lookup_value(&MEDIA_KEY_NAMES, &name) {
+            return Ok(Self::Media(media));
+        }
+        let (direction, modifier) = if let Some(modifier) = name.strip_prefix("left") {
+            (ModifierDirection::Left, modifier)
+        } else if let Some(modifier) = name.strip_prefix("right") {
+            (ModifierDirection::Right, modifier)
+        } else {
+            (ModifierDirection::Unknown, name.as_str())
+        };
+        if let Some(modifier) = lookup_value(&MODIFIER_KEY_NAMES, modifier) {
+            return Ok(Self::Modifier(modifier, direction));
+        }
+        Err(NotationError::UnknownKey {
+            name: s.to_string(),
+        })
+    }
+}
+
+/// Formats the modifiers as a list of names separated by `+`, such as `ctrl+shift`. Empty
+/// modifiers are formatted as an empty string.
+impl fmt::Display for KeyModifiers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names = MODIFIER_NAMES
+            .iter()
+            .filter(|(modifier, _)| self.contains(*modifier))
+            .map(|(_, name)| name);
+        if let Some(name) = names.next() {
+            f.write_str(name)?;
+        }
+        for name in names {
+            write!(f, "+{name}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a list of modifier names separated by `+`, such as `ctrl+shift`. Names are
+/// case-insensitive. An empty string is parsed as [`KeyModifiers::NONE`].
+impl FromStr for KeyModifiers {
+    type Err = NotationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = Self::NONE;
+        if s.is_empty() {
+            return Ok(modifiers);
+        }
+        for name in s.split('+') {
+            let modifier = parse_modifier(name).ok_or_else(|| NotationError::UnknownModifier {
+                name: name.to_string(),
+            })?;
+            add_modifier(&mut modifiers, modifier, name)?;
+        }
+        Ok(modifiers)
+    }
+}
+
+/// Formats the key event in its canonical notation. Modifiers are listed before the key and
+/// separated by `+`, such as `ctrl+shift+a`.
+///
+/// Events from the keypad are prefixed with `keypad`, and the `capslock` and `numlock` states are
+/// included the same way. Release and repeat events are prefixed with `release` or `repeat`.
+/// Uppercase letters are written as `shift` combined with the lowercase letter.
+///
+/// ```
+/// use terminput::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+///
+/// let key_event = KeyEvent::new(KeyCode::Char('A')).modifiers(KeyModifiers::CTRL);
+/// assert_eq!(key_event.to_string(), "ctrl+shift+a");
+///
+/// let key_event = KeyEvent::new(KeyCode::Enter).kind(KeyEventKind::Release);
+/// assert_eq!(key_event.to_string(), "release+enter");
+/// ```
+impl fmt::Display for KeyEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let key_event = self.normalize_case();
+        match key_event.kind {
+            KeyEventKind::Press => {}
+            KeyEventKind::Repeat => f.write_str("repeat+")?,
+            KeyEventKind::Release => f.write_str("release+")?,
+        }
+        if !key_event.modifiers.is_empty() {
+            write!(f, "{}+", key_event.modifiers)?;
+        }
+        for (state, name) in STATE_NAMES {
+            if key_event.state.contains(state) {
+                write!(f, "{name}+")?;
+            }
+        }
+        match key_event.code {
+            KeyCode::Char(c) if c.is_ascii_uppercase() => write!(f, "{}", c.to_ascii_lowercase()),
+            code => write!(f, "{code}"),
+        }
+    }
+}
+
+/// Parses a key event from its canonical notation, such as `ctrl+shift+a`. See the
+/// [`Display`](fmt::Display) implementation for details.
+///
+/// Modifiers can be given in any order and are case-insensitive. The `control`, `option`, and
+/// `cmd` aliases are also accepted. The result is normalized using [`KeyEvent::normalize_case`],
+/// so `ctrl+A` is the same as `ctrl+shift+a`.
+///
+/// ```
+/// use terminput::{KeyCode, KeyEvent, KeyModifiers};
+///
+/// let key_event: KeyEvent = "ctrl+A".parse().unwrap();
+/// assert_eq!(key_event, "ctrl+shift+a".parse().unwrap());
+/// assert_eq!(
+///     key_event,
+///     KeyEvent::new(KeyCode::Char('a')).modifiers(KeyModifiers::CTRL | KeyModifiers::SHIFT)
+/// );
+/// ```
+impl FromStr for KeyEvent {
+    type Err = NotationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let missing_key = || NotationError::MissingKey {
+            input: s.to_string(),
+        };
+        // The key is always last, so a trailing `+` is the key itself
+        let (prefixes, key) = if s == "+" {
+            ("", "+")
+        } else if let Some(prefixes) = s.strip_suffix("++") {
+            (prefixes, "+")
+        } else {
+            match s.rsplit_once('+') {
+                Some((_, "")) => return Err(missing_key()),
+                Some((prefixes, key)) => (prefixes, key),
+                None if s.is_empty() => return Err(missing_key()),
+                None => ("", s),
+            }
+        };
+
+        let mut key_event = Self::new(key.parse()?);
+        if prefixes.is_empty() {
+            return Ok(key_event.normalize_case());
+        }
+        for name in prefixes.split('+') {
+            let lowercase = name.to_ascii_lowercase();
+            if let Some(modifier) = parse_modifier(&lowercase) {
+                add_modifier(&mut key_event.modifiers, modifier, name)?;
+            } else if let Some(state) = lookup_value(&STATE_NAMES, &lowercase) {
+                if key_event.state.contains(state) {
+                    return Err(NotationError::DuplicateModifier {
+                        name: name.to_string(),
+                    });
+                }
+                key_event.state |= state;
+            } else if let Some(kind) = parse_kind(&lowercase) {
+                if key_event.kind != KeyEventKind::Press {
+                    return Err(NotationError::DuplicateModifier {
+                        name: name.to_string(),
+                    });
+                }
+                key_event.kind = kind;
+            } else if name.is_empty() {
+                return Err(missing_key());
+            } else {
+                return Err(NotationError::UnknownModifier {
+                    name: name.to_string(),
+                });
+            }
+        }
+        Ok(key_event.normalize_case())
+    }
+}
+
+fn parse_modifier(name: &str) -> Option<KeyModifiers> {
+    let name = name.to_ascii_lowercase();
+    match name.as_str() {
+        "control" => Some(KeyModifiers::CTRL),
+        "option" => Some(KeyModifiers::ALT),
+        "cmd" => Some(KeyModifiers::SUPER),
+        _ => lookup_value(&MODIFIER_NAMES, &name),
+    }
+}
+
+fn add_modifier(
+    modifiers: &mut KeyModifiers,
+    modifier: KeyModifiers,
+    name: &str,
+) -> Result<(), NotationError> {
+    if modifiers.contains(modifier) {
+        return Err(NotationError::DuplicateModifier {
+            name: name.to_string(),
+        });
+    }
+    *modifiers |= modifier;
+    Ok(())
+}
+
+fn parse_kind(name: &str) -> Option<KeyEventKind> {
+    match name {
+        "repeat" => Some(KeyEventKind::Repeat),
+        "release" => Some(KeyEventKind::Release),
+        _ => None,
+    }
+}
+
+fn lookup_name<T>(names: &[(T, &'static str)], value: &T) -> &'static str
+where
+    T: PartialEq,
+{
+    find_name(names, value).unwrap_or_default()
+}
+
+fn find_name<T>(names: &[(T, &'static str)], value: &T) -> Option<&'static str>
+where
+    T: PartialEq,
+{
+    names
+        .iter()
+        .find(|(v, _)| v == value)
+        .map(|(_, name)| *name)
+}
+
+fn lookup_value<T>(names: &[(T, &str)], name: &str) -> Option<T>
+where
+    T: Copy,
+{
+    names.iter().find(|(_, n)| *n == name).map(|(v, _)| *v)
+}
+
+fn lookup_value_ignore_case<T>(names: &[(T, &str)], name: &str) -> Option<T>
+where
+    T: Copy,
+{
+    names
+        .iter()
+        .find(|(_, n)| n.eq_ignore_ascii_case(name))
+        .map(|(v, _)| *v)
+}
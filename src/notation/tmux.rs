@@ -0,0 +1,245 @@
+use super::{NotationError, find_name, lookup_value_ignore_case};
+use crate::{KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers};
+
+// Modifiers in the order that tmux writes them
+const TMUX_MODIFIERS: [(KeyModifiers, &str); 3] = [
+    (KeyModifiers::CTRL, "C-"),
+    (KeyModifiers::ALT, "M-"),
+    (KeyModifiers::SHIFT, "S-"),
+];
+
+// The first name for each key is the one used for formatting
+const TMUX_KEY_NAMES: [(KeyCode, &str); 23] = [
+    (KeyCode::Insert, "IC"),
+    (KeyCode::Delete, "DC"),
+    (KeyCode::Home, "Home"),
+    (KeyCode::End, "End"),
+    (KeyCode::PageDown, "NPage"),
+    (KeyCode::PageUp, "PPage"),
+    (KeyCode::Tab, "Tab"),
+    (KeyCode::Char(' '), "Space"),
+    (KeyCode::Backspace, "BSpace"),
+    (KeyCode::Enter, "Enter"),
+    (KeyCode::Esc, "Escape"),
+    (KeyCode::Up, "Up"),
+    (KeyCode::Down, "Down"),
+    (KeyCode::Left, "Left"),
+    (KeyCode::Right, "Right"),
+    (KeyCode::Insert, "Insert"),
+    (KeyCode::Delete, "Delete"),
+    (KeyCode::PageDown, "PageDown"),
+    (KeyCode::PageDown, "PgDn"),
+    (KeyCode::PageUp, "PageUp"),
+    (KeyCode::PageUp, "PgUp"),
+    (KeyCode::Enter, "Return"),
+    (KeyCode::Esc, "Esc"),
+];
+
+// Keys with the `KEYPAD` state
+const TMUX_KEYPAD_NAMES: [(KeyCode, &str); 16] = [
+    (KeyCode::Char('/'), "KP/"),
+    (KeyCode::Char('*'), "KP*"),
+    (KeyCode::Char('-'), "KP-"),
+    (KeyCode::Char('+'), "KP+"),
+    (KeyCode::Char('.'), "KP."),
+    (KeyCode::Enter, "KPEnter"),
+    (KeyCode::Char('0'), "KP0"),
+    (KeyCode::Char('1'), "KP1"),
+    (KeyCode::Char('2'), "KP2"),
+    (KeyCode::Char('3'), "KP3"),
+    (KeyCode::Char('4'), "KP4"),
+    (KeyCode::Char('5'), "KP5"),
+    (KeyCode::Char('6'), "KP6"),
+    (KeyCode::Char('7'), "KP7"),
+    (KeyCode::Char('8'), "KP8"),
+    (KeyCode::Char('9'), "KP9"),
+];
+
+/// Converts between key sequences and tmux's key notation, as used by `send-keys` and `bind-key`,
+/// such as `C-M-x`, `BSpace`, or `S-F3`.
+///
+/// Keys in a sequence are separated by whitespace, and each key can be combined with the `C-`
+/// (control), `M-` (alt), and `S-` (shift) modifiers. `^x` is accepted as a shorthand for `C-x`.
+/// Key names are case-insensitive. `BTab` is parsed as Shift+Tab, and keys on the keypad use the
+/// `KP` names, such as `KP5` or `KPEnter`, which map to [`KeyEventState::KEYPAD`]. Control
+/// combined with a letter is case-insensitive unless `S-` is given, so `C-A` is the same as `C-a`.
+/// As with `send-keys`, a word without modifiers that isn't a key name is treated as a sequence
+/// of characters, so `C-b ab` is three keys.
+///
+/// ```
+/// use terminput::{KeyCode, KeyEvent, KeyModifiers, TmuxNotation};
+///
+/// let notation = TmuxNotation::new();
+/// let keys = notation.parse("C-M-x BTab").unwrap();
+/// assert_eq!(
+///     keys,
+///     vec![
+///         KeyEvent::new(KeyCode::Char('x')).modifiers(KeyModifiers::CTRL | KeyModifiers::ALT),
+///         KeyEvent::new(KeyCode::Tab).modifiers(KeyModifiers::SHIFT),
+///     ]
+/// );
+/// assert_eq!(notation.format(&keys).unwrap(), "C-M-x BTab");
+/// ```
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct TmuxNotation;
+
+impl TmuxNotation {
+    /// Creates a new [`TmuxNotation`].
+    pub const fn new() -> Self {
+        Self
+    }
+
+    /// Parses a sequence of keys separated by whitespace, such as `C-b c`.
+    ///
+    /// Returns an error if the sequence is empty or contains an unknown key name or modifier.
+    pub fn parse(&self, notation: &str) -> Result<Vec<KeyEvent>, NotationError> {
+        let mut keys = Vec::new();
+        for word in notation.split_whitespace() {
+            self.parse_word(word, &mut keys)?;
+        }
+        if keys.is_empty() {
+            return Err(NotationError::MissingKey {
+                input: notation.to_string(),
+            });
+        }
+        Ok(keys)
+    }
+
+    /// Formats a sequence of keys separated by spaces, such as `C-b c`.
+    ///
+    /// Returns an error if any of the keys can't be represented in tmux notation, such as media
+    /// keys or key releases.
+    pub fn format(&self, keys: &[KeyEvent]) -> Result<String, NotationError> {
+        let keys = keys
+            .iter()
+            .map(|key| self.format_key(key))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(keys.join(" "))
+    }
+
+    /// Formats a single key in tmux notation, such as `C-M-x` or `NPage`. Caps Lock and Num Lock
+    /// states are ignored.
+    ///
+    /// Returns an error if the key can't be represented in tmux notation. tmux only supports the
+    /// control, alt, and shift modifiers and function keys up to F12, and has no names for media
+    /// keys, modifier keys, or key releases.
+    pub fn format_key(&self, key_event: &KeyEvent) -> Result<String, NotationError> {
+        let unsupported = || NotationError::UnsupportedKey {
+            key: key_event.to_string(),
+        };
+        let key_event = key_event.normalize_case();
+        let mut modifiers = key_event.modifiers;
+        if key_event.kind != KeyEventKind::Press
+            || !(modifiers - (KeyModifiers::CTRL | KeyModifiers::ALT | KeyModifiers::SHIFT))
+                .is_empty()
+        {
+            return Err(unsupported());
+        }
+        let keypad_name = if key_event.state.contains(KeyEventState::KEYPAD) {
+            find_name(&TMUX_KEYPAD_NAMES, &key_event.code)
+        } else {
+            None
+        };
+        let name = match (keypad_name, key_event.code) {
+            (Some(name), _) => name.to_string(),
+            (None, KeyCode::Tab) if modifiers.contains(KeyModifiers::SHIFT) => {
+                modifiers.remove(KeyModifiers::SHIFT);
+                "BTab".to_string()
+            }
+            (None, KeyCode::Char(c)) if c.is_control() => return Err(unsupported()),
+            (None, KeyCode::Char(c)) => {
+                let mut c = c;
+                if c.is_ascii_uppercase() && modifiers.contains(KeyModifiers::CTRL) {
+                    // C-A means the same thing as C-a, so shift needs to be explicit
+                    c = c.to_ascii_lowercase();
+                } else if c.is_ascii_uppercase() {
+                    modifiers.remove(KeyModifiers::SHIFT);
+                }
+                find_name(&TMUX_KEY_NAMES, &KeyCode::Char(c))
+                    .map(ToString::to_string)
+                    .unwrap_or_else(|| c.to_string())
+            }
+            (None, KeyCode::F(n @ 1..=12)) => format!("F{n}"),
+            (None, code) => find_name(&TMUX_KEY_NAMES, &code)
+                .ok_or_else(unsupported)?
+                .to_string(),
+        };
+
+        let mut formatted = String::new();
+        for (modifier, prefix) in TMUX_MODIFIERS {
+            if modifiers.contains(modifier) {
+                formatted.push_str(prefix);
+            }
+        }
+        formatted.push_str(&name);
+        Ok(formatted)
+    }
+
+    fn parse_word(&self, word: &str, keys: &mut Vec<KeyEvent>) -> Result<(), NotationError> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut name = word;
+        loop {
+            let modifier = match name.as_bytes() {
+                [b'^', _, ..] => {
+                    name = &name[1..];
+                    KeyModifiers::CTRL
+                }
+                [prefix, b'-', _, ..] => {
+                    let modifier = match prefix.to_ascii_uppercase() {
+                        b'C' => KeyModifiers::CTRL,
+                        b'M' => KeyModifiers::ALT,
+                        b'S' => KeyModifiers::SHIFT,
+                        _ => {
+                            return Err(NotationError::UnknownModifier {
+                                name: name[..1].to_string(),
+                            });
+                        }
+                    };
+                    name = &name[2..];
+                    modifier
+                }
+                _ => break,
+            };
+            modifiers |= modifier;
+        }
+
+        let mut state = KeyEventState::NONE;
+        let mut chars = name.chars();
+        let code = if let (Some(c), None) = (chars.next(), chars.next()) {
+            // Control is case-insensitive unless shift is given explicitly
+            if modifiers.contains(KeyModifiers::CTRL) && !modifiers.contains(KeyModifiers::SHIFT) {
+                KeyCode::Char(c.to_ascii_lowercase())
+            } else {
+                KeyCode::Char(c)
+            }
+        } else if name.eq_ignore_ascii_case("BTab") {
+            modifiers |= KeyModifiers::SHIFT;
+            KeyCode::Tab
+        } else if let Some(code) = lookup_value_ignore_case(&TMUX_KEYPAD_NAMES, name) {
+            state = KeyEventState::KEYPAD;
+            code
+        } else if let Some(code) = lookup_value_ignore_case(&TMUX_KEY_NAMES, name) {
+            code
+        } else if let Some(Ok(n @ 1..=12)) = name.strip_prefix(['F', 'f']).map(str::parse) {
+            KeyCode::F(n)
+        } else if modifiers.is_empty() {
+            // A word of plain characters
+            keys.extend(
+                name.chars()
+                    .map(|c| KeyEvent::new(KeyCode::Char(c)).normalize_case()),
+            );
+            return Ok(());
+        } else {
+            return Err(NotationError::UnknownKey {
+                name: name.to_string(),
+            });
+        };
+        keys.push(
+            KeyEvent::new(code)
+                .modifiers(modifiers)
+                .state(state)
+                .normalize_case(),
+        );
+        Ok(())
+    }
+}
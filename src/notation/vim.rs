@@ -0,0 +1,325 @@
+use super::{NotationError, find_name, lookup_value_ignore_case};
+use crate::{KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers};
+
+// Modifiers in the order that Vim writes them
+const VIM_MODIFIERS: [(KeyModifiers, char); 5] = [
+    (KeyModifiers::ALT, 'M'),
+    (KeyModifiers::META, 'T'),
+    (KeyModifiers::CTRL, 'C'),
+    (KeyModifiers::SHIFT, 'S'),
+    (KeyModifiers::SUPER, 'D'),
+];
+
+// The first name for each key is the one used for formatting
+const VIM_KEY_NAMES: [(KeyCode, &str); 31] = [
+    (KeyCode::Backspace, "BS"),
+    (KeyCode::Tab, "Tab"),
+    (KeyCode::Enter, "CR"),
+    (KeyCode::Esc, "Esc"),
+    (KeyCode::Char(' '), "Space"),
+    (KeyCode::Char('<'), "lt"),
+    (KeyCode::Char('\\'), "Bslash"),
+    (KeyCode::Char('|'), "Bar"),
+    (KeyCode::Delete, "Del"),
+    (KeyCode::Insert, "Insert"),
+    (KeyCode::Home, "Home"),
+    (KeyCode::End, "End"),
+    (KeyCode::PageUp, "PageUp"),
+    (KeyCode::PageDown, "PageDown"),
+    (KeyCode::Up, "Up"),
+    (KeyCode::Down, "Down"),
+    (KeyCode::Left, "Left"),
+    (KeyCode::Right, "Right"),
+    (KeyCode::KeypadBegin, "kOrigin"),
+    (KeyCode::Backspace, "Backspace"),
+    (KeyCode::Enter, "Return"),
+    (KeyCode::Enter, "Enter"),
+    (KeyCode::Delete, "Delete"),
+    (KeyCode::Insert, "Ins"),
+    (KeyCode::Char('\\'), "Backslash"),
+    (KeyCode::Char('<'), "Lt"),
+    (KeyCode::Char(' '), "Spc"),
+    (KeyCode::Esc, "Escape"),
+    (KeyCode::PageUp, "PgUp"),
+    (KeyCode::PageDown, "PgDn"),
+    (KeyCode::KeypadBegin, "kBegin"),
+];
+
+// Keys with the `KEYPAD` state
+const VIM_KEYPAD_NAMES: [(KeyCode, &str); 29] = [
+    (KeyCode::Up, "kUp"),
+    (KeyCode::Down, "kDown"),
+    (KeyCode::Left, "kLeft"),
+    (KeyCode::Right, "kRight"),
+    (KeyCode::Home, "kHome"),
+    (KeyCode::End, "kEnd"),
+    (KeyCode::PageUp, "kPageUp"),
+    (KeyCode::PageDown, "kPageDown"),
+    (KeyCode::Delete, "kDel"),
+    (KeyCode::Insert, "kInsert"),
+    (KeyCode::KeypadBegin, "kOrigin"),
+    (KeyCode::Enter, "kEnter"),
+    (KeyCode::Char('+'), "kPlus"),
+    (KeyCode::Char('-'), "kMinus"),
+    (KeyCode::Char('*'), "kMultiply"),
+    (KeyCode::Char('/'), "kDivide"),
+    (KeyCode::Char('.'), "kPoint"),
+    (KeyCode::Char(','), "kComma"),
+    (KeyCode::Char('='), "kEqual"),
+    (KeyCode::Char('0'), "k0"),
+    (KeyCode::Char('1'), "k1"),
+    (KeyCode::Char('2'), "k2"),
+    (KeyCode::Char('3'), "k3"),
+    (KeyCode::Char('4'), "k4"),
+    (KeyCode::Char('5'), "k5"),
+    (KeyCode::Char('6'), "k6"),
+    (KeyCode::Char('7'), "k7"),
+    (KeyCode::Char('8'), "k8"),
+    (KeyCode::Char('9'), "k9"),
+];
+
+/// Converts between key sequences and Vim's
+/// [key notation](https://vimhelp.org/intro.txt.html#key-notation), as used in mappings.
+///
+/// Special keys are written in angle brackets, such as `<CR>` or `<F12>`, and can be combined
+/// with the `S-` (shift), `C-` (control), `M-` or `A-` (alt), `T-` (meta), and `D-` (super)
+/// modifiers, such as `<C-S-Tab>`. Any other characters are treated as individual keys. Keys on
+/// the keypad use the `<k...>` names, such as `<kEnter>` or `<k5>`, which map to
+/// [`KeyEventState::KEYPAD`].
+///
+/// `<leader>` and `<localleader>` are replaced with the configured [leader](VimNotation::leader)
+/// keys, which default to a backslash like they do in Vim. `<Nop>` doesn't produce any keys.
+///
+/// As in Vim, anything in angle brackets that isn't a known key is treated as literal
+/// characters, and control combined with a letter is case-insensitive, so `<C-A>` is the same as
+/// `<C-a>`. Use `<C-S-a>` for control and shift.
+///
+/// ```
+/// use terminput::{KeyCode, KeyEvent, KeyModifiers, VimNotation};
+///
+/// let notation = VimNotation::new().leader(KeyEvent::new(KeyCode::Char(' ')));
+/// let keys = notation.parse("<leader>gd<C-w>j").unwrap();
+/// assert_eq!(
+///     keys,
+///     vec![
+///         KeyEvent::new(KeyCode::Char(' ')),
+///         KeyEvent::new(KeyCode::Char('g')),
+///         KeyEvent::new(KeyCode::Char('d')),
+///         KeyEvent::new(KeyCode::Char('w')).modifiers(KeyModifiers::CTRL),
+///         KeyEvent::new(KeyCode::Char('j')),
+///     ]
+/// );
+/// assert_eq!(notation.format(&keys).unwrap(), "<Space>gd<C-w>j");
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct VimNotation {
+    leader: KeyEvent,
+    local_leader: KeyEvent,
+}
+
+impl Default for VimNotation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl VimNotation {
+    /// Creates a new [`VimNotation`] that uses a backslash for the leader keys.
+    pub const fn new() -> Self {
+        Self {
+            leader: KeyEvent::new(KeyCode::Char('\\')),
+            local_leader: KeyEvent::new(KeyCode::Char('\\')),
+        }
+    }
+
+    /// Sets the key that `<leader>` is replaced with, the same as Vim's `mapleader` variable.
+    pub const fn leader(mut self, leader: KeyEvent) -> Self {
+        self.leader = leader;
+        self
+    }
+
+    /// Sets the key that `<localleader>` is replaced with, the same as Vim's `maplocalleader`
+    /// variable.
+    pub const fn local_leader(mut self, local_leader: KeyEvent) -> Self {
+        self.local_leader = local_leader;
+        self
+    }
+
+    /// Parses a sequence of keys in Vim notation, such as `<C-w>j`.
+    ///
+    /// Returns an error if the sequence is empty.
+    pub fn parse(&self, notation: &str) -> Result<Vec<KeyEvent>, NotationError> {
+        if notation.is_empty() {
+            return Err(NotationError::MissingKey {
+                input: String::new(),
+            });
+        }
+        let mut keys = Vec::new();
+        let mut rest = notation;
+        while let Some(c) = rest.chars().next() {
+            if c == '<' {
+                if let Some((len, key)) = self.parse_special_key(rest) {
+                    keys.extend(key);
+                    rest = &rest[len..];
+                    continue;
+                }
+            }
+            keys.push(KeyEvent::new(KeyCode::Char(c)).normalize_case());
+            rest = &rest[c.len_utf8()..];
+        }
+        Ok(keys)
+    }
+
+    /// Formats a sequence of keys in Vim notation.
+    ///
+    /// Returns an error if any of the keys can't be represented in Vim notation, such as media
+    /// keys or key releases.
+    pub fn format(&self, keys: &[KeyEvent]) -> Result<String, NotationError> {
+        keys.iter().map(|key| self.format_key(key)).collect()
+    }
+
+    /// Formats a single key in Vim notation, such as `<C-a>` or `x`. Caps Lock and Num Lock
+    /// states are ignored.
+    ///
+    /// Returns an error if the key can't be represented in Vim notation, including control
+    /// characters that aren't combined with a key, such as `\x01`.
+    pub fn format_key(&self, key_event: &KeyEvent) -> Result<String, NotationError> {
+        let unsupported = || NotationError::UnsupportedKey {
+            key: key_event.to_string(),
+        };
+        let key_event = key_event.normalize_case();
+        let mut modifiers = key_event.modifiers;
+        if key_event.kind != KeyEventKind::Press || modifiers.contains(KeyModifiers::HYPER) {
+            return Err(unsupported());
+        }
+        let keypad_name = if key_event.state.contains(KeyEventState::KEYPAD) {
+            find_name(&VIM_KEYPAD_NAMES, &key_event.code)
+        } else {
+            None
+        };
+        let name = match (keypad_name, key_event.code) {
+            (Some(name), _) => name.to_string(),
+            (None, KeyCode::Char(c)) if c.is_control() => return Err(unsupported()),
+            (None, KeyCode::Char(c)) => {
+                let mut c = c;
+                if c.is_ascii_uppercase() && modifiers.contains(KeyModifiers::CTRL) {
+                    // <C-A> means the same thing as <C-a>, so shift needs to be explicit
+                    c = c.to_ascii_lowercase();
+                } else if c.is_ascii_uppercase() {
+                    modifiers.remove(KeyModifiers::SHIFT);
+                }
+                match find_name(&VIM_KEY_NAMES, &KeyCode::Char(c)) {
+                    Some(name) => name.to_string(),
+                    None if modifiers.is_empty() => return Ok(c.to_string()),
+                    None => c.to_string(),
+                }
+            }
+            (None, KeyCode::F(n)) => format!("F{n}"),
+            (None, code) => find_name(&VIM_KEY_NAMES, &code)
+                .ok_or_else(unsupported)?
+                .to_string(),
+        };
+
+        let mut formatted = String::from("<");
+        for (modifier, prefix) in VIM_MODIFIERS {
+            if modifiers.contains(modifier) {
+                formatted.push(prefix);
+                formatted.push('-');
+            }
+        }
+        formatted.push_str(&name);
+        formatted.push('>');
+        Ok(formatted)
+    }
+
+    // Parses the key in angle brackets at the start of the input. Returns the length of the
+    // notation and the keys that it represents, or `None` if it should be treated as literal
+    // characters.
+    fn parse_special_key(&self, input: &str) -> Option<(usize, Vec<KeyEvent>)> {
+        let mut close = input[1..].find('>')? + 1;
+        // A modifier combined with `>`, such as `<C->>`
+        if input[..close].ends_with('-') && input[close + 1..].starts_with('>') {
+            close += 1;
+        }
+        let inner = &input[1..close];
+        let len = close + 1;
+
+        match inner.to_ascii_lowercase().as_str() {
+            "leader" => return Some((len, vec![self.leader])),
+            "localleader" => return Some((len, vec![self.local_leader])),
+            "nop" => return Some((len, Vec::new())),
+            _ => {}
+        }
+
+        let mut modifiers = KeyModifiers::NONE;
+        let mut name = inner;
+        while let [prefix, b'-', _, ..] = name.as_bytes() {
+            modifiers |= match prefix.to_ascii_lowercase() {
+                b's' => KeyModifiers::SHIFT,
+                b'c' => KeyModifiers::CTRL,
+                b'm' | b'a' => KeyModifiers::ALT,
+                b't' => KeyModifiers::META,
+                b'd' => KeyModifiers::SUPER,
+                _ => return None,
+            };
+            name = &name[2..];
+        }
+
+        let mut state = KeyEventState::NONE;
+        let mut chars = name.chars();
+        let code = if let (Some(c), None) = (chars.next(), chars.next()) {
+            // A character without modifiers isn't a key name, so `<a>` is three keys
+            if modifiers.is_empty() {
+                return None;
+            }
+            // Control is case-insensitive unless shift is given explicitly
+            if modifiers.contains(KeyModifiers::CTRL) && !modifiers.contains(KeyModifiers::SHIFT) {
+                KeyCode::Char(c.to_ascii_lowercase())
+            } else {
+                KeyCode::Char(c)
+            }
+        } else if let Some(code) = lookup_value_ignore_case(&VIM_KEYPAD_NAMES, name) {
+            state = KeyEventState::KEYPAD;
+            code
+        } else if let Some(code) = lookup_value_ignore_case(&VIM_KEY_NAMES, name) {
+            code
+        } else {
+            let lowercase = name.to_ascii_lowercase();
+            match lowercase.as_str() {
+                // Both of these are control characters that are usually sent with Ctrl
+                "nul" => {
+                    modifiers |= KeyModifiers::CTRL;
+                    KeyCode::Char(' ')
+                }
+                "nl" | "newline" | "linefeed" => {
+                    modifiers |= KeyModifiers::CTRL;
+                    KeyCode::Char('j')
+                }
+                _ => {
+                    if let Some(n) = lowercase.strip_prefix('f') {
+                        KeyCode::F(n.parse().ok()?)
+                    } else if let Some(n) = lowercase.strip_prefix("char-") {
+                        KeyCode::Char(parse_char_code(n)?)
+                    } else {
+                        return None;
+                    }
+                }
+            }
+        };
+        let key_event = KeyEvent::new(code).modifiers(modifiers).state(state);
+        Some((len, vec![key_event.normalize_case()]))
+    }
+}
+
+// Parses the character code used in `<Char-N>`, which can be decimal, octal with a leading `0`,
+// or hexadecimal with a leading `0x`
+fn parse_char_code(code: &str) -> Option<char> {
+    let code = if let Some(hex) = code.strip_prefix("0x") {
+        u32::from_str_radix(hex, 16).ok()?
+    } else if code.len() > 1 && code.starts_with('0') {
+        u32::from_str_radix(&code[1..], 8).ok()?
+    } else {
+        code.parse().ok()?
+    };
+    char::from_u32(code)
+}
@@ -0,0 +1,114 @@
+use std::error::Error;
+use std::ops::Range;
+use std::{fmt, io};
+
+/// An error that occurred while parsing input.
+///
+/// Each variant contains the range of bytes in the input that caused the error. The range is
+/// relative to the start of the buffer that was passed to the parser.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ParseError {
+    /// The input contained invalid UTF-8.
+    InvalidUtf8 {
+        /// The range of the invalid bytes.
+        range: Range<usize>,
+    },
+    /// A CSI sequence ended with a final byte that isn't recognized.
+    UnknownCsiFinal {
+        /// The final byte of the sequence.
+        byte: u8,
+        /// The range of the sequence.
+        range: Range<usize>,
+    },
+    /// A numeric parameter was missing or could not be parsed.
+    MalformedParameter {
+        /// The range of the parameter, or the range of the sequence if the parameter was missing.
+        range: Range<usize>,
+    },
+    /// A mouse event contained a button that isn't supported.
+    UnsupportedMouseButton {
+        /// The button number that was reported by the terminal.
+        button: u8,
+        /// The range of the sequence.
+        range: Range<usize>,
+    },
+    /// The sequence is a response to a query sent to the terminal, not an input event.
+    TerminalResponse {
+        /// The range of the sequence.
+        range: Range<usize>,
+    },
+    /// The sequence is well-formed, but doesn't represent any known input event.
+    UnknownSequence {
+        /// The range of the sequence.
+        range: Range<usize>,
+    },
+    /// The input was flushed before an escape sequence or UTF-8 character was complete.
+    IncompleteSequence {
+        /// The range of the incomplete input.
+        range: Range<usize>,
+    },
+}
+
+impl ParseError {
+    /// Returns the range of bytes that caused the error.
+    pub fn range(&self) -> Range<usize> {
+        match self {
+            Self::InvalidUtf8 { range }
+            | Self::UnknownCsiFinal { range, .. }
+            | Self::MalformedParameter { range }
+            | Self::UnsupportedMouseButton { range, .. }
+            | Self::TerminalResponse { range }
+            | Self::UnknownSequence { range }
+            | Self::IncompleteSequence { range } => range.clone(),
+        }
+    }
+
+    pub(crate) fn offset(mut self, offset: usize) -> Self {
+        match &mut self {
+            Self::InvalidUtf8 { range }
+            | Self::UnknownCsiFinal { range, .. }
+            | Self::MalformedParameter { range }
+            | Self::UnsupportedMouseButton { range, .. }
+            | Self::TerminalResponse { range }
+            | Self::UnknownSequence { range }
+            | Self::IncompleteSequence { range } => {
+                *range = range.start + offset..range.end + offset;
+            }
+        }
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidUtf8 { range } => write!(f, "Invalid UTF-8 at bytes {range:?}"),
+            Self::UnknownCsiFinal { byte, range } => write!(
+                f,
+                "Unknown CSI final byte {:?} at bytes {range:?}",
+                *byte as char
+            ),
+            Self::MalformedParameter { range } => {
+                write!(f, "Malformed parameter at bytes {range:?}")
+            }
+            Self::UnsupportedMouseButton { button, range } => {
+                write!(f, "Unsupported mouse button {button} at bytes {range:?}")
+            }
+            Self::TerminalResponse { range } => {
+                write!(f, "Unexpected terminal response at bytes {range:?}")
+            }
+            Self::UnknownSequence { range } => write!(f, "Unknown sequence at bytes {range:?}"),
+            Self::IncompleteSequence { range } => {
+                write!(f, "Incomplete sequence at bytes {range:?}")
+            }
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+impl From<ParseError> for io::Error {
+    fn from(value: ParseError) -> Self {
+        Self::new(io::ErrorKind::InvalidData, value)
+    }
+}
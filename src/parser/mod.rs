@@ -1,15 +1,42 @@
 // This is a lightly modified version of crossterm's ansi escape sequence parser:
 // https://github.com/crossterm-rs/crossterm/blob/master/src/event/sys/unix/parse.rs
 
-use std::io;
+use std::ops::Range;
 
 use crate::{
-    Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers, MediaKeyCode,
-    ModifierDirection, ModifierKeyCode, MouseButton, MouseEvent, MouseEventKind,
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers, KeyText, MediaKeyCode,
+    ModifierDirection, ModifierKeyCode, MouseButton, MouseEncoding, MouseEvent, MouseEventKind,
+    PixelPosition, PixelSize, TerminalResponse,
 };
 
-fn could_not_parse_event_error() -> io::Error {
-    io::Error::new(io::ErrorKind::Other, "Could not parse an event.")
+mod error;
+mod stream;
+
+pub use error::*;
+pub use stream::*;
+
+fn unknown_sequence(buffer: &[u8]) -> ParseError {
+    ParseError::UnknownSequence {
+        range: 0..buffer.len(),
+    }
+}
+
+fn malformed_parameter(buffer: &[u8]) -> ParseError {
+    ParseError::MalformedParameter {
+        range: 0..buffer.len(),
+    }
+}
+
+// Gets the position of a parameter within the buffer it was split from.
+fn param_range(buffer: &[u8], param: &str) -> Range<usize> {
+    let start = param.as_ptr() as usize - buffer.as_ptr() as usize;
+    start..start + param.len()
+}
+
+fn params_str(buffer: &[u8], range: Range<usize>) -> Result<&str, ParseError> {
+    std::str::from_utf8(&buffer[range.clone()]).map_err(|e| ParseError::InvalidUtf8 {
+        range: range.start + e.valid_up_to()..range.end,
+    })
 }
 
 /// Attempts to parse a byte sequence into an input event.
@@ -18,8 +45,24 @@ fn could_not_parse_event_error() -> io::Error {
 ///
 /// Returns [`None`] if the input could be a valid event, but is incomplete.
 ///
-/// Returns an [`io::Error`] if the input cannot be parsed into an input event.
-pub fn parse_event(buffer: &[u8]) -> io::Result<Option<Event>> {
+/// Returns a [`ParseError`] if the input cannot be parsed into an input event.
+pub fn parse_event(buffer: &[u8]) -> Result<Option<Event>, ParseError> {
+    parse_event_with(buffer, ParseContext::default())
+}
+
+// Additional state used to resolve sequences that have more than one meaning.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct ParseContext {
+    // Treat `CSI 1 ; m R` as a cursor position report instead of F3 with modifiers.
+    pub(crate) expect_cursor_position: bool,
+    // Used to decode `CSI M` sequences, which have the same prefix in X10 and UTF-8 mode.
+    pub(crate) mouse_encoding: MouseEncoding,
+}
+
+pub(crate) fn parse_event_with(
+    buffer: &[u8],
+    context: ParseContext,
+) -> Result<Option<Event>, ParseError> {
     if buffer.is_empty() {
         return Ok(None);
     }
@@ -45,11 +88,37 @@ pub fn parse_event(buffer: &[u8]) -> io::Result<Option<Event>> {
                                 val @ b'P'..=b'S' => {
                                     Ok(Some(Event::Key(KeyCode::F(1 + val - b'P').into())))
                                 }
-                                _ => Err(could_not_parse_event_error()),
+                                // Keypad keys in application keypad mode
+                                b'M' => Ok(Some(Event::Key(
+                                    KeyEvent::new(KeyCode::Enter).state(KeyEventState::KEYPAD),
+                                ))),
+                                val @ (b'X' | b'j'..=b'y') => {
+                                    let c = match val {
+                                        b'X' => '=',
+                                        b'j'..=b'o' => {
+                                            char::from(b"*+,-./"[usize::from(val - b'j')])
+                                        }
+                                        _ => char::from(val - b'p' + b'0'),
+                                    };
+                                    Ok(Some(Event::Key(
+                                        KeyEvent::new(KeyCode::Char(c))
+                                            .state(KeyEventState::KEYPAD),
+                                    )))
+                                }
+                                _ => Err(unknown_sequence(&buffer[..3])),
                             }
                         }
                     }
-                    b'[' => parse_csi(buffer),
+                    b'[' => parse_csi(buffer, context),
+                    b'P' if buffer.starts_with(b"\x1BP>|") => parse_dcs_terminal_version(buffer),
+                    // Other string sequences (DCS, OSC, etc.) aren't valid input events. These are
+                    // only treated as a sequence if they're terminated, otherwise the input is
+                    // treated as an Alt-modified key.
+                    b']' | b'P' | b'X' | b'^' | b'_'
+                        if buffer.len() > 2 && is_string_terminated(buffer) =>
+                    {
+                        Err(unknown_sequence(buffer))
+                    }
                     b'\x1B' => {
                         if buffer.len() == 2 {
                             Ok(Some(Event::Key(
@@ -57,25 +126,28 @@ pub fn parse_event(buffer: &[u8]) -> io::Result<Option<Event>> {
                             )))
                         } else {
                             match &buffer[2..] {
+                                b"[" => Ok(None),
                                 b"[Z" => Ok(Some(Event::Key(
                                     KeyEvent::new(KeyCode::Tab)
                                         .modifiers(KeyModifiers::SHIFT | KeyModifiers::ALT),
                                 ))),
-                                _ => Err(could_not_parse_event_error()),
+                                _ => Err(unknown_sequence(buffer)),
                             }
                         }
                     }
-                    _ => parse_event(&buffer[1..]).map(|event_option| {
-                        event_option.map(|event| {
-                            if let Event::Key(key_event) = event {
-                                let mut alt_key_event = key_event;
-                                alt_key_event.modifiers |= KeyModifiers::ALT;
-                                Event::Key(alt_key_event)
-                            } else {
-                                event
-                            }
-                        })
-                    }),
+                    _ => parse_event_with(&buffer[1..], context)
+                        .map_err(|e| e.offset(1))
+                        .map(|event_option| {
+                            event_option.map(|event| {
+                                if let Event::Key(key_event) = event {
+                                    let mut alt_key_event = key_event;
+                                    alt_key_event.modifiers |= KeyModifiers::ALT;
+                                    Event::Key(alt_key_event)
+                                } else {
+                                    event
+                                }
+                            })
+                        }),
                 }
             }
         }
@@ -107,6 +179,11 @@ pub fn parse_event(buffer: &[u8]) -> io::Result<Option<Event>> {
     }
 }
 
+// Checks if a string sequence ends with ST, or with BEL in the case of OSC.
+fn is_string_terminated(buffer: &[u8]) -> bool {
+    buffer.ends_with(b"\x1B\\") || (buffer.starts_with(b"\x1B]") && buffer.ends_with(b"\x07"))
+}
+
 // converts KeyCode to KeyEvent (adds shift modifier in case of uppercase characters)
 fn char_code_to_event(code: KeyCode) -> KeyEvent {
     let modifiers = match code {
@@ -116,7 +193,7 @@ fn char_code_to_event(code: KeyCode) -> KeyEvent {
     KeyEvent::new(code).modifiers(modifiers)
 }
 
-pub(crate) fn parse_csi(buffer: &[u8]) -> io::Result<Option<Event>> {
+pub(crate) fn parse_csi(buffer: &[u8], context: ParseContext) -> Result<Option<Event>, ParseError> {
     assert!(buffer.starts_with(b"\x1B[")); // ESC [
 
     if buffer.len() == 2 {
@@ -132,7 +209,7 @@ pub(crate) fn parse_csi(buffer: &[u8]) -> io::Result<Option<Event>> {
                     // NOTE (@imdaveho): cannot find when this occurs;
                     // having another '[' after ESC[ not a likely scenario
                     val @ b'A'..=b'E' => Some(Event::Key(KeyCode::F(1 + val - b'A').into())),
-                    _ => return Err(could_not_parse_event_error()),
+                    _ => return Err(unknown_sequence(&buffer[..4])),
                 }
             }
         }
@@ -145,8 +222,11 @@ pub(crate) fn parse_csi(buffer: &[u8]) -> io::Result<Option<Event>> {
         b'Z' => Some(Event::Key(
             KeyEvent::new(KeyCode::Tab).modifiers(KeyModifiers::SHIFT),
         )),
+        b'M' if context.mouse_encoding == MouseEncoding::Utf8 => {
+            return parse_csi_utf8_mouse(buffer);
+        }
         b'M' => return parse_csi_normal_mouse(buffer),
-        b'<' => return parse_csi_sgr_mouse(buffer),
+        b'<' => return parse_csi_sgr_mouse(buffer, context),
         b'I' => Some(Event::FocusGained),
         b'O' => Some(Event::FocusLost),
         b';' => return parse_csi_modifier_key_code(buffer),
@@ -159,10 +239,18 @@ pub(crate) fn parse_csi(buffer: &[u8]) -> io::Result<Option<Event>> {
         b'R' => Some(Event::Key(KeyCode::F(3).into())),
         b'S' => Some(Event::Key(KeyCode::F(4).into())),
         b'?' => match buffer[buffer.len() - 1] {
-            // Keyboard enhancement flags, not a valid input event
-            b'u' => return Err(could_not_parse_event_error()),
-            // Primary device attributes, not a valid input event
-            b'c' => return Err(could_not_parse_event_error()),
+            b'u' => return parse_csi_kitty_keyboard_flags(buffer),
+            b'c' => return parse_csi_primary_device_attributes(buffer),
+            _ => None,
+        },
+        b'>' => match buffer[buffer.len() - 1] {
+            b'c' => return parse_csi_secondary_device_attributes(buffer),
+            // Other sequences in this format are replies to queries, such as XTQMODKEYS
+            0x40..=0x7E => {
+                return Err(ParseError::TerminalResponse {
+                    range: 0..buffer.len(),
+                });
+            }
             _ => None,
         },
         b'0'..=b'9' => {
@@ -183,42 +271,74 @@ pub(crate) fn parse_csi(buffer: &[u8]) -> io::Result<Option<Event>> {
                         b'M' => return parse_csi_rxvt_mouse(buffer),
                         b'~' => return parse_csi_special_key_code(buffer),
                         b'u' => return parse_csi_u_encoded_key_code(buffer),
+                        b'R' => return parse_csi_cursor_position(buffer, context),
+                        b't' => return parse_csi_resize(buffer),
                         _ => return parse_csi_modifier_key_code(buffer),
                     }
                 }
             }
         }
-        _ => return Err(could_not_parse_event_error()),
+        byte @ 0x40..=0x7E => {
+            return Err(ParseError::UnknownCsiFinal { byte, range: 0..3 });
+        }
+        _ => return Err(unknown_sequence(buffer)),
     };
 
     Ok(input_event)
 }
 
-pub(crate) fn next_parsed<T>(iter: &mut dyn Iterator<Item = &str>) -> io::Result<T>
+pub(crate) fn next_parsed<T>(
+    iter: &mut dyn Iterator<Item = &str>,
+    buffer: &[u8],
+) -> Result<T, ParseError>
 where
     T: std::str::FromStr,
 {
-    iter.next()
-        .ok_or_else(could_not_parse_event_error)?
+    let param = iter.next().ok_or_else(|| malformed_parameter(buffer))?;
+    parse_param(param, buffer)
+}
+
+fn parse_param<T>(param: &str, buffer: &[u8]) -> Result<T, ParseError>
+where
+    T: std::str::FromStr,
+{
+    param
         .parse::<T>()
-        .map_err(|_| could_not_parse_event_error())
+        .map_err(|_| ParseError::MalformedParameter {
+            range: param_range(buffer, param),
+        })
 }
 
-fn modifier_and_kind_parsed(iter: &mut dyn Iterator<Item = &str>) -> io::Result<(u8, u8)> {
+fn modifier_and_kind_parsed(
+    iter: &mut dyn Iterator<Item = &str>,
+    buffer: &[u8],
+) -> Result<(u8, u8), ParseError> {
     let mut sub_split = iter
         .next()
-        .ok_or_else(could_not_parse_event_error)?
+        .ok_or_else(|| malformed_parameter(buffer))?
         .split(':');
 
-    let modifier_mask = next_parsed::<u8>(&mut sub_split)?;
+    let modifier_mask = next_parsed::<u8>(&mut sub_split, buffer)?;
 
-    if let Ok(kind_code) = next_parsed::<u8>(&mut sub_split) {
+    if let Ok(kind_code) = next_parsed::<u8>(&mut sub_split, buffer) {
         Ok((modifier_mask, kind_code))
     } else {
         Ok((modifier_mask, 1))
     }
 }
 
+// Mouse coordinates are 1-based, so they need to be converted to 0-based values.
+fn next_coordinate(iter: &mut dyn Iterator<Item = &str>, buffer: &[u8]) -> Result<u16, ParseError> {
+    let param = iter.next().ok_or_else(|| malformed_parameter(buffer))?;
+    param
+        .parse::<u16>()
+        .ok()
+        .and_then(|coordinate| coordinate.checked_sub(1))
+        .ok_or_else(|| ParseError::MalformedParameter {
+            range: param_range(buffer, param),
+        })
+}
+
 fn parse_modifiers(mask: u8) -> KeyModifiers {
     let modifier_mask = mask.saturating_sub(1);
     let mut modifiers = KeyModifiers::empty();
@@ -264,28 +384,27 @@ fn parse_key_event_kind(kind: u8) -> KeyEventKind {
     }
 }
 
-pub(crate) fn parse_csi_modifier_key_code(buffer: &[u8]) -> io::Result<Option<Event>> {
+pub(crate) fn parse_csi_modifier_key_code(buffer: &[u8]) -> Result<Option<Event>, ParseError> {
     assert!(buffer.starts_with(b"\x1B[")); // ESC [
 
-    let s = std::str::from_utf8(&buffer[2..buffer.len() - 1])
-        .map_err(|_| could_not_parse_event_error())?;
+    let s = params_str(buffer, 2..buffer.len() - 1)?;
     let mut split = s.split(';');
 
     split.next();
 
     let (modifiers, kind) =
-        if let Ok((modifier_mask, kind_code)) = modifier_and_kind_parsed(&mut split) {
+        if let Ok((modifier_mask, kind_code)) = modifier_and_kind_parsed(&mut split, buffer) {
             (
                 parse_modifiers(modifier_mask),
                 parse_key_event_kind(kind_code),
             )
         } else if buffer.len() > 3 {
             (
-                parse_modifiers(
-                    (buffer[buffer.len() - 2] as char)
-                        .to_digit(10)
-                        .ok_or_else(could_not_parse_event_error)? as u8,
-                ),
+                parse_modifiers((buffer[buffer.len() - 2] as char).to_digit(10).ok_or(
+                    ParseError::MalformedParameter {
+                        range: buffer.len() - 2..buffer.len() - 1,
+                    },
+                )? as u8),
                 KeyEventKind::Press,
             )
         } else {
@@ -304,7 +423,12 @@ pub(crate) fn parse_csi_modifier_key_code(buffer: &[u8]) -> io::Result<Option<Ev
         b'Q' => KeyCode::F(2),
         b'R' => KeyCode::F(3),
         b'S' => KeyCode::F(4),
-        _ => return Err(could_not_parse_event_error()),
+        byte => {
+            return Err(ParseError::UnknownCsiFinal {
+                byte,
+                range: 0..buffer.len(),
+            });
+        }
     };
 
     let input_event = Event::Key(KeyEvent::new(keycode).modifiers(modifiers).kind(kind));
@@ -455,7 +579,7 @@ fn translate_functional_key_code(codepoint: u32) -> Option<(KeyCode, KeyEventSta
     None
 }
 
-pub(crate) fn parse_csi_u_encoded_key_code(buffer: &[u8]) -> io::Result<Option<Event>> {
+pub(crate) fn parse_csi_u_encoded_key_code(buffer: &[u8]) -> Result<Option<Event>, ParseError> {
     assert!(buffer.starts_with(b"\x1B[")); // ESC [
     assert!(buffer.ends_with(b"u"));
 
@@ -463,8 +587,7 @@ pub(crate) fn parse_csi_u_encoded_key_code(buffer: &[u8]) -> io::Result<Option<E
     // the `CSI u` (a.k.a. "Fix Keyboard Input on Terminals - Please", https://www.leonerd.org.uk/hacks/fixterms/)
     // or Kitty Keyboard Protocol (https://sw.kovidgoyal.net/kitty/keyboard-protocol/) specifications.
     // This CSI sequence is a tuple of semicolon-separated numbers.
-    let s = std::str::from_utf8(&buffer[2..buffer.len() - 1])
-        .map_err(|_| could_not_parse_event_error())?;
+    let s = params_str(buffer, 2..buffer.len() - 1)?;
     let mut split = s.split(';');
 
     // In `CSI u`, this is parsed as:
@@ -478,17 +601,21 @@ pub(crate) fn parse_csi_u_encoded_key_code(buffer: &[u8]) -> io::Result<Option<E
     //     CSI unicode-key-code:alternate-key-codes ; modifiers:event-type ; text-as-codepoints u
     let mut codepoints = split
         .next()
-        .ok_or_else(could_not_parse_event_error)?
+        .ok_or_else(|| malformed_parameter(buffer))?
         .split(':');
 
-    let codepoint = codepoints
+    let codepoint_str = codepoints
         .next()
-        .ok_or_else(could_not_parse_event_error)?
+        .ok_or_else(|| malformed_parameter(buffer))?;
+    let codepoint_range = param_range(buffer, codepoint_str);
+    let codepoint = codepoint_str
         .parse::<u32>()
-        .map_err(|_| could_not_parse_event_error())?;
+        .map_err(|_| ParseError::MalformedParameter {
+            range: codepoint_range.clone(),
+        })?;
 
     let (mut modifiers, kind, state_from_modifiers) =
-        if let Ok((modifier_mask, kind_code)) = modifier_and_kind_parsed(&mut split) {
+        if let Ok((modifier_mask, kind_code)) = modifier_and_kind_parsed(&mut split, buffer) {
             (
                 parse_modifiers(modifier_mask),
                 parse_key_event_kind(kind_code),
@@ -498,7 +625,7 @@ pub(crate) fn parse_csi_u_encoded_key_code(buffer: &[u8]) -> io::Result<Option<E
             (KeyModifiers::NONE, KeyEventKind::Press, KeyEventState::NONE)
         };
 
-    let (mut keycode, state_from_keycode) = {
+    let (keycode, state_from_keycode) = {
         if let Some((special_key_code, state)) = translate_functional_key_code(codepoint) {
             (special_key_code, state)
         } else if let Some(c) = char::from_u32(codepoint) {
@@ -519,7 +646,9 @@ pub(crate) fn parse_csi_u_encoded_key_code(buffer: &[u8]) -> io::Result<Option<E
                 KeyEventState::empty(),
             )
         } else {
-            return Err(could_not_parse_event_error());
+            return Err(ParseError::MalformedParameter {
+                range: codepoint_range,
+            });
         }
     };
 
@@ -547,44 +676,95 @@ pub(crate) fn parse_csi_u_encoded_key_code(buffer: &[u8]) -> io::Result<Option<E
         }
     }
 
-    // When the "report alternate keys" flag is enabled in the Kitty Keyboard Protocol
-    // and the terminal sends a keyboard event containing shift, the sequence will
-    // contain an additional codepoint separated by a ':' character which contains
-    // the shifted character according to the keyboard layout.
-    if modifiers.contains(KeyModifiers::SHIFT) {
-        if let Some(shifted_c) = codepoints
+    // When the "report alternate keys" flag is enabled in the Kitty Keyboard Protocol, the
+    // sequence may contain additional codepoints separated by ':' characters. The first one
+    // contains the shifted character according to the keyboard layout and is only sent when
+    // shift is pressed. The second one contains the key in the standard PC-101 layout and is
+    // only sent if it's different from the primary key.
+    let mut alternate_key = || {
+        codepoints
             .next()
             .and_then(|codepoint| codepoint.parse::<u32>().ok())
             .and_then(char::from_u32)
-        {
-            keycode = KeyCode::Char(shifted_c);
-            modifiers.set(KeyModifiers::SHIFT, false);
-        }
-    }
+    };
+    let shifted_key = alternate_key();
+    let base_layout_key = alternate_key();
 
-    let input_event = Event::Key(
-        KeyEvent::new(keycode)
-            .modifiers(modifiers)
-            .kind(kind)
-            .state(state_from_keycode | state_from_modifiers),
-    );
+    // When the "report associated text" flag is enabled in the Kitty Keyboard Protocol, the
+    // third field contains the text generated by the key as a list of ':'-separated codepoints.
+    let text = match split.next() {
+        Some(text) if !text.is_empty() => parse_key_text(text, buffer)?,
+        _ => None,
+    };
 
-    Ok(Some(input_event))
+    let mut key_event = KeyEvent::new(keycode)
+        .modifiers(modifiers)
+        .kind(kind)
+        .state(state_from_keycode | state_from_modifiers);
+    key_event.text = text;
+    key_event.shifted_key = shifted_key;
+    key_event.base_layout_key = base_layout_key;
+
+    Ok(Some(Event::Key(key_event)))
+}
+
+fn parse_key_text(param: &str, buffer: &[u8]) -> Result<Option<KeyText>, ParseError> {
+    let mut text = String::new();
+    for codepoint in param.split(':') {
+        let c = char::from_u32(parse_param(codepoint, buffer)?).ok_or_else(|| {
+            ParseError::MalformedParameter {
+                range: param_range(buffer, codepoint),
+            }
+        })?;
+        text.push(c);
+    }
+    // Text that doesn't fit is an error rather than being silently dropped
+    KeyText::new(&text)
+        .map(Some)
+        .ok_or_else(|| ParseError::MalformedParameter {
+            range: param_range(buffer, param),
+        })
 }
 
-pub(crate) fn parse_csi_special_key_code(buffer: &[u8]) -> io::Result<Option<Event>> {
+fn parse_csi_modify_other_keys(buffer: &[u8], s: &str) -> Result<Option<Event>, ParseError> {
+    // Xterm's modifyOtherKeys encoding: CSI 27 ; modifiers ; codepoint ~
+    // https://invisible-island.net/xterm/modified-keys.html
+    let mut split = s.split(';').skip(1);
+    let modifier_mask = next_parsed::<u8>(&mut split, buffer)?;
+    let codepoint_str = split.next().ok_or_else(|| malformed_parameter(buffer))?;
+    let codepoint = parse_param::<u32>(codepoint_str, buffer)?;
+    let keycode = match char::from_u32(codepoint) {
+        Some('\x1B') => KeyCode::Esc,
+        Some('\r') => KeyCode::Enter,
+        Some('\t') => KeyCode::Tab,
+        Some('\x7F' | '\x08') => KeyCode::Backspace,
+        Some(c) => KeyCode::Char(c),
+        None => {
+            return Err(ParseError::MalformedParameter {
+                range: param_range(buffer, codepoint_str),
+            });
+        }
+    };
+    Ok(Some(Event::Key(
+        KeyEvent::new(keycode).modifiers(parse_modifiers(modifier_mask)),
+    )))
+}
+
+pub(crate) fn parse_csi_special_key_code(buffer: &[u8]) -> Result<Option<Event>, ParseError> {
     assert!(buffer.starts_with(b"\x1B[")); // ESC [
     assert!(buffer.ends_with(b"~"));
 
-    let s = std::str::from_utf8(&buffer[2..buffer.len() - 1])
-        .map_err(|_| could_not_parse_event_error())?;
+    let s = params_str(buffer, 2..buffer.len() - 1)?;
+    if s.starts_with("27;") {
+        return parse_csi_modify_other_keys(buffer, s);
+    }
     let mut split = s.split(';');
 
     // This CSI sequence can be a list of semicolon-separated numbers.
-    let first = next_parsed::<u8>(&mut split)?;
+    let first = next_parsed::<u8>(&mut split, buffer)?;
 
     let (modifiers, kind, state) =
-        if let Ok((modifier_mask, kind_code)) = modifier_and_kind_parsed(&mut split) {
+        if let Ok((modifier_mask, kind_code)) = modifier_and_kind_parsed(&mut split, buffer) {
             (
                 parse_modifiers(modifier_mask),
                 parse_key_event_kind(kind_code),
@@ -606,7 +786,7 @@ pub(crate) fn parse_csi_special_key_code(buffer: &[u8]) -> io::Result<Option<Eve
         v @ 23..=26 => KeyCode::F(v - 12),
         v @ 28..=29 => KeyCode::F(v - 15),
         v @ 31..=34 => KeyCode::F(v - 17),
-        _ => return Err(could_not_parse_event_error()),
+        _ => return Err(unknown_sequence(buffer)),
     };
 
     let input_event = Event::Key(
@@ -619,34 +799,34 @@ pub(crate) fn parse_csi_special_key_code(buffer: &[u8]) -> io::Result<Option<Eve
     Ok(Some(input_event))
 }
 
-pub(crate) fn parse_csi_rxvt_mouse(buffer: &[u8]) -> io::Result<Option<Event>> {
+pub(crate) fn parse_csi_rxvt_mouse(buffer: &[u8]) -> Result<Option<Event>, ParseError> {
     // rxvt mouse encoding:
     // ESC [ Cb ; Cx ; Cy ; M
 
     assert!(buffer.starts_with(b"\x1B[")); // ESC [
     assert!(buffer.ends_with(b"M"));
 
-    let s = std::str::from_utf8(&buffer[2..buffer.len() - 1])
-        .map_err(|_| could_not_parse_event_error())?;
+    let s = params_str(buffer, 2..buffer.len() - 1)?;
     let mut split = s.split(';');
 
-    let cb = next_parsed::<u8>(&mut split)?
+    let cb = next_parsed::<u8>(&mut split, buffer)?
         .checked_sub(32)
-        .ok_or_else(could_not_parse_event_error)?;
-    let (kind, modifiers) = parse_cb(cb)?;
+        .ok_or_else(|| malformed_parameter(buffer))?;
+    let (kind, modifiers) = parse_cb(cb, buffer)?;
 
-    let cx = next_parsed::<u16>(&mut split)? - 1;
-    let cy = next_parsed::<u16>(&mut split)? - 1;
+    let cx = next_coordinate(&mut split, buffer)?;
+    let cy = next_coordinate(&mut split, buffer)?;
 
     Ok(Some(Event::Mouse(MouseEvent {
         kind,
         column: cx,
         row: cy,
         modifiers,
+        pixel_position: None,
     })))
 }
 
-pub(crate) fn parse_csi_normal_mouse(buffer: &[u8]) -> io::Result<Option<Event>> {
+pub(crate) fn parse_csi_normal_mouse(buffer: &[u8]) -> Result<Option<Event>, ParseError> {
     // Normal mouse encoding: ESC [ M CB Cx Cy (6 characters only).
 
     assert!(buffer.starts_with(b"\x1B[M")); // ESC [ M
@@ -657,24 +837,78 @@ pub(crate) fn parse_csi_normal_mouse(buffer: &[u8]) -> io::Result<Option<Event>>
 
     let cb = buffer[3]
         .checked_sub(32)
-        .ok_or_else(could_not_parse_event_error)?;
-    let (kind, modifiers) = parse_cb(cb)?;
+        .ok_or(ParseError::MalformedParameter { range: 3..4 })?;
+    let (kind, modifiers) = parse_cb(cb, &buffer[..6])?;
 
     // See http://www.xfree86.org/current/ctlseqs.html#Mouse%20Tracking
     // The upper left character position on the terminal is denoted as 1,1.
     // Subtract 1 to keep it synced with cursor
-    let cx = u16::from(buffer[4].saturating_sub(32)) - 1;
-    let cy = u16::from(buffer[5].saturating_sub(32)) - 1;
+    let cx = u16::from(buffer[4].saturating_sub(32))
+        .checked_sub(1)
+        .ok_or(ParseError::MalformedParameter { range: 4..5 })?;
+    let cy = u16::from(buffer[5].saturating_sub(32))
+        .checked_sub(1)
+        .ok_or(ParseError::MalformedParameter { range: 5..6 })?;
 
     Ok(Some(Event::Mouse(MouseEvent {
         kind,
         column: cx,
         row: cy,
         modifiers,
+        pixel_position: None,
+    })))
+}
+
+pub(crate) fn parse_csi_utf8_mouse(buffer: &[u8]) -> Result<Option<Event>, ParseError> {
+    // UTF-8 extended mouse encoding: ESC [ M Cb Cx Cy, where each value is a UTF-8 encoded
+    // character with a length of one or two bytes.
+
+    assert!(buffer.starts_with(b"\x1B[M")); // ESC [ M
+
+    let mut values = [0; 3];
+    let mut start = 3;
+    for value in &mut values {
+        let Some(&first) = buffer.get(start) else {
+            return Ok(None);
+        };
+        let len = if first < 0x80 { 1 } else { 2 };
+        let Some(bytes) = buffer.get(start..start + len) else {
+            return Ok(None);
+        };
+        let range = start..start + len;
+        *value = std::str::from_utf8(bytes)
+            .ok()
+            .and_then(|c| c.chars().next())
+            .and_then(|c| u16::try_from(c).ok())
+            .and_then(|c| c.checked_sub(32))
+            .ok_or(ParseError::MalformedParameter { range })?;
+        start += len;
+    }
+    let buffer = &buffer[..start];
+
+    let cb = u8::try_from(values[0]).map_err(|_| malformed_parameter(buffer))?;
+    let (kind, modifiers) = parse_cb(cb, buffer)?;
+    // The upper left character position on the terminal is denoted as 1,1.
+    // Subtract 1 to keep it synced with cursor
+    let [column, row] = [values[1], values[2]].map(|value| {
+        value
+            .checked_sub(1)
+            .ok_or_else(|| malformed_parameter(buffer))
+    });
+
+    Ok(Some(Event::Mouse(MouseEvent {
+        kind,
+        column: column?,
+        row: row?,
+        modifiers,
+        pixel_position: None,
     })))
 }
 
-pub(crate) fn parse_csi_sgr_mouse(buffer: &[u8]) -> io::Result<Option<Event>> {
+pub(crate) fn parse_csi_sgr_mouse(
+    buffer: &[u8],
+    context: ParseContext,
+) -> Result<Option<Event>, ParseError> {
     // ESC [ < Cb ; Cx ; Cy (;) (M or m)
 
     assert!(buffer.starts_with(b"\x1B[<")); // ESC [ <
@@ -683,18 +917,17 @@ pub(crate) fn parse_csi_sgr_mouse(buffer: &[u8]) -> io::Result<Option<Event>> {
         return Ok(None);
     }
 
-    let s = std::str::from_utf8(&buffer[3..buffer.len() - 1])
-        .map_err(|_| could_not_parse_event_error())?;
+    let s = params_str(buffer, 3..buffer.len() - 1)?;
     let mut split = s.split(';');
 
-    let cb = next_parsed::<u8>(&mut split)?;
-    let (kind, modifiers) = parse_cb(cb)?;
+    let cb = next_parsed::<u8>(&mut split, buffer)?;
+    let (kind, modifiers) = parse_cb(cb, buffer)?;
 
     // See http://www.xfree86.org/current/ctlseqs.html#Mouse%20Tracking
     // The upper left character position on the terminal is denoted as 1,1.
     // Subtract 1 to keep it synced with cursor
-    let cx = next_parsed::<u16>(&mut split)? - 1;
-    let cy = next_parsed::<u16>(&mut split)? - 1;
+    let cx = next_coordinate(&mut split, buffer)?;
+    let cy = next_coordinate(&mut split, buffer)?;
 
     // When button 3 in Cb is used to represent mouse release, you can't tell which button was
     // released. SGR mode solves this by having the sequence end with a lowercase m if it's a
@@ -711,11 +944,23 @@ pub(crate) fn parse_csi_sgr_mouse(buffer: &[u8]) -> io::Result<Option<Event>> {
         kind
     };
 
+    // SGR-Pixels uses the same format, but the position is in pixels
+    if context.mouse_encoding == MouseEncoding::SgrPixels {
+        return Ok(Some(Event::Mouse(MouseEvent {
+            kind,
+            column: 0,
+            row: 0,
+            modifiers,
+            pixel_position: Some(PixelPosition { x: cx, y: cy }),
+        })));
+    }
+
     Ok(Some(Event::Mouse(MouseEvent {
         kind,
         column: cx,
         row: cy,
         modifiers,
+        pixel_position: None,
     })))
 }
 
@@ -732,7 +977,7 @@ pub(crate) fn parse_csi_sgr_mouse(buffer: &[u8]) -> io::Result<Option<Event>> {
 /// - mouse is dragging
 /// - button number
 /// - button number
-fn parse_cb(cb: u8) -> io::Result<(MouseEventKind, KeyModifiers)> {
+fn parse_cb(cb: u8, buffer: &[u8]) -> Result<(MouseEventKind, KeyModifiers), ParseError> {
     let button_number = (cb & 0b0000_0011) | ((cb & 0b1100_0000) >> 4);
     let dragging = cb & 0b0010_0000 == 0b0010_0000;
 
@@ -744,13 +989,24 @@ fn parse_cb(cb: u8) -> io::Result<(MouseEventKind, KeyModifiers)> {
         (1, true) => MouseEventKind::Drag(MouseButton::Middle),
         (2, true) => MouseEventKind::Drag(MouseButton::Right),
         (3, false) => MouseEventKind::Up(MouseButton::Left),
+        (8, false) => MouseEventKind::Down(MouseButton::Back),
+        (9, false) => MouseEventKind::Down(MouseButton::Forward),
+        (button @ (10 | 11), false) => MouseEventKind::Down(MouseButton::Other(button)),
+        (8, true) => MouseEventKind::Drag(MouseButton::Back),
+        (9, true) => MouseEventKind::Drag(MouseButton::Forward),
+        (button @ (10 | 11), true) => MouseEventKind::Drag(MouseButton::Other(button)),
         (3, true) | (4, true) | (5, true) => MouseEventKind::Moved,
         (4, false) => MouseEventKind::ScrollUp,
         (5, false) => MouseEventKind::ScrollDown,
         (6, false) => MouseEventKind::ScrollLeft,
         (7, false) => MouseEventKind::ScrollRight,
         // We do not support other buttons.
-        _ => return Err(could_not_parse_event_error()),
+        _ => {
+            return Err(ParseError::UnsupportedMouseButton {
+                button: button_number,
+                range: 0..buffer.len(),
+            });
+        }
     };
 
     let mut modifiers = KeyModifiers::empty();
@@ -768,7 +1024,7 @@ fn parse_cb(cb: u8) -> io::Result<(MouseEventKind, KeyModifiers)> {
     Ok((kind, modifiers))
 }
 
-pub(crate) fn parse_csi_bracketed_paste(buffer: &[u8]) -> io::Result<Option<Event>> {
+pub(crate) fn parse_csi_bracketed_paste(buffer: &[u8]) -> Result<Option<Event>, ParseError> {
     // ESC [ 2 0 0 ~ pasted text ESC 2 0 1 ~
     assert!(buffer.starts_with(b"\x1B[200~"));
 
@@ -780,10 +1036,145 @@ pub(crate) fn parse_csi_bracketed_paste(buffer: &[u8]) -> io::Result<Option<Even
     }
 }
 
-pub(crate) fn parse_utf8_char(buffer: &[u8]) -> io::Result<Option<char>> {
+pub(crate) fn parse_csi_resize(buffer: &[u8]) -> Result<Option<Event>, ParseError> {
+    // In-band resize notification (mode 2048): CSI 48 ; rows ; cols ; height ; width t
+    // Text area size report (XTWINOPS): CSI 8 ; rows ; cols t
+    assert!(buffer.starts_with(b"\x1B[")); // ESC [
+    assert!(buffer.ends_with(b"t"));
+
+    let s = params_str(buffer, 2..buffer.len() - 1)?;
+    let mut split = s.split(';');
+    let kind = next_parsed::<u8>(&mut split, buffer)?;
+    if kind != 8 && kind != 48 {
+        return Err(unknown_sequence(buffer));
+    }
+    let rows = next_parsed::<u32>(&mut split, buffer)?;
+    let cols = next_parsed::<u32>(&mut split, buffer)?;
+    let pixel_size = if kind == 48 {
+        let height = next_parsed::<u32>(&mut split, buffer)?;
+        let width = next_parsed::<u32>(&mut split, buffer)?;
+        // Terminals report 0 if the pixel size isn't known
+        (height > 0 || width > 0).then_some(PixelSize { width, height })
+    } else {
+        None
+    };
+    Ok(Some(Event::Resize {
+        rows,
+        cols,
+        pixel_size,
+    }))
+}
+
+pub(crate) fn parse_csi_cursor_position(
+    buffer: &[u8],
+    context: ParseContext,
+) -> Result<Option<Event>, ParseError> {
+    // ESC [ row ; column R
+    assert!(buffer.starts_with(b"\x1B["));
+    assert!(buffer.ends_with(b"R"));
+
+    let s = params_str(buffer, 2..buffer.len() - 1)?;
+    // Reports for the first row look the same as F3 with modifiers, so they're only treated as
+    // a cursor position if one was requested.
+    let is_report = match s.split_once(';') {
+        Some(("1", _)) => context.expect_cursor_position,
+        Some(_) => true,
+        None => false,
+    };
+    if !is_report {
+        return parse_csi_modifier_key_code(buffer);
+    }
+    let mut split = s.split(';');
+    let row = next_coordinate(&mut split, buffer)?;
+    let column = next_coordinate(&mut split, buffer)?;
+
+    Ok(Some(Event::Response(TerminalResponse::CursorPosition {
+        row,
+        column,
+    })))
+}
+
+pub(crate) fn parse_csi_kitty_keyboard_flags(buffer: &[u8]) -> Result<Option<Event>, ParseError> {
+    // ESC [ ? flags u
+    assert!(buffer.starts_with(b"\x1B[?"));
+    assert!(buffer.ends_with(b"u"));
+
+    let s = params_str(buffer, 3..buffer.len() - 1)?;
+    let flags = parse_param::<u8>(s, buffer)?.into();
+
+    Ok(Some(Event::Response(
+        TerminalResponse::KittyKeyboardFlags { flags },
+    )))
+}
+
+pub(crate) fn parse_csi_primary_device_attributes(
+    buffer: &[u8],
+) -> Result<Option<Event>, ParseError> {
+    // ESC [ ? Ps ; ... c
+    assert!(buffer.starts_with(b"\x1B[?"));
+    assert!(buffer.ends_with(b"c"));
+
+    let s = params_str(buffer, 3..buffer.len() - 1)?;
+    let mut split = s.split(';');
+    let conformance_level = next_parsed(&mut split, buffer)?;
+    let features = split
+        .map(|param| parse_param(param, buffer))
+        .collect::<Result<_, _>>()?;
+
+    Ok(Some(Event::Response(
+        TerminalResponse::PrimaryDeviceAttributes {
+            conformance_level,
+            features,
+        },
+    )))
+}
+
+pub(crate) fn parse_csi_secondary_device_attributes(
+    buffer: &[u8],
+) -> Result<Option<Event>, ParseError> {
+    // ESC [ > Pp ; Pv ; Pc c
+    assert!(buffer.starts_with(b"\x1B[>"));
+    assert!(buffer.ends_with(b"c"));
+
+    let s = params_str(buffer, 3..buffer.len() - 1)?;
+    let mut split = s.split(';');
+    let terminal_type = next_parsed(&mut split, buffer)?;
+    let firmware_version = next_parsed(&mut split, buffer)?;
+    // Some terminals omit the last parameter since it's always 0
+    let rom_cartridge = split
+        .next()
+        .map_or(Ok(0), |param| parse_param(param, buffer))?;
+
+    Ok(Some(Event::Response(
+        TerminalResponse::SecondaryDeviceAttributes {
+            terminal_type,
+            firmware_version,
+            rom_cartridge,
+        },
+    )))
+}
+
+pub(crate) fn parse_dcs_terminal_version(buffer: &[u8]) -> Result<Option<Event>, ParseError> {
+    // ESC P > | text ESC \
+    assert!(buffer.starts_with(b"\x1BP>|"));
+
+    if !buffer.ends_with(b"\x1B\\") {
+        return Ok(None);
+    }
+    let version = params_str(buffer, 4..buffer.len() - 2)?.to_string();
+
+    Ok(Some(Event::Response(TerminalResponse::TerminalVersion {
+        version,
+    })))
+}
+
+pub(crate) fn parse_utf8_char(buffer: &[u8]) -> Result<Option<char>, ParseError> {
+    let invalid_utf8 = || ParseError::InvalidUtf8 {
+        range: 0..buffer.len(),
+    };
     match std::str::from_utf8(buffer) {
         Ok(s) => {
-            let ch = s.chars().next().ok_or_else(could_not_parse_event_error)?;
+            let ch = s.chars().next().ok_or_else(invalid_utf8)?;
 
             Ok(Some(ch))
         }
@@ -797,14 +1188,14 @@ pub(crate) fn parse_utf8_char(buffer: &[u8]) -> io::Result<Option<char>> {
                 (0xC0..=0xDF) => 2, // 110xxxxx 10xxxxxx
                 (0xE0..=0xEF) => 3, // 1110xxxx 10xxxxxx 10xxxxxx
                 (0xF0..=0xF7) => 4, // 11110xxx 10xxxxxx 10xxxxxx 10xxxxxx
-                (0x80..=0xBF) | (0xF8..=0xFF) => return Err(could_not_parse_event_error()),
+                (0x80..=0xBF) | (0xF8..=0xFF) => return Err(invalid_utf8()),
             };
 
             // More than 1 byte, check them for 10xxxxxx pattern
             if required_bytes > 1 && buffer.len() > 1 {
                 for byte in &buffer[1..] {
                     if byte & !0b0011_1111 != 0b1000_0000 {
-                        return Err(could_not_parse_event_error());
+                        return Err(invalid_utf8());
                     }
                 }
             }
@@ -813,7 +1204,7 @@ pub(crate) fn parse_utf8_char(buffer: &[u8]) -> io::Result<Option<char>> {
                 // All bytes looks good so far, but we need more of them
                 Ok(None)
             } else {
-                Err(could_not_parse_event_error())
+                Err(invalid_utf8())
             }
         }
     }
@@ -0,0 +1,523 @@
+use std::time::{Duration, Instant};
+
+use super::{ParseContext, parse_event_with};
+use crate::{Event, KeyModifiers, MouseEncoding, ParseError, TerminalResponse};
+
+/// A stateful parser that can handle input containing any number of events.
+///
+/// Unlike [`parse_event`](crate::parse_event), which expects the buffer to contain exactly one
+/// event, the parser accepts arbitrary chunks of input. Any bytes that make up an incomplete
+/// sequence at the end of a chunk are kept until the rest of the sequence arrives in a subsequent
+/// call to [`Parser::parse`].
+///
+/// ```
+/// use terminput::{Event, KeyCode, Parser};
+///
+/// let mut parser = Parser::new();
+/// let events: Vec<_> = parser
+///     .parse(b"\x1B[A\x1B[")
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(events, vec![(Event::Key(KeyCode::Up.into()), 3)]);
+///
+/// let events: Vec<_> = parser.parse(b"B").collect::<Result<_, _>>().unwrap();
+/// assert_eq!(events, vec![(Event::Key(KeyCode::Down.into()), 3)]);
+/// ```
+///
+/// # Escape key handling
+///
+/// The escape key sends the same byte that's used to start escape sequences, so a lone `ESC` is
+/// ambiguous until more input arrives. By default, an `ESC` at the end of the input is returned as
+/// [`KeyCode::Esc`](crate::KeyCode::Esc) immediately, while incomplete sequences such as `ESC [`
+/// are kept until they're completed.
+///
+/// If an [escape timeout](Parser::escape_timeout) is set, any pending input that begins with
+/// `ESC` is kept until either more input arrives or the timeout elapses, similar to vim's
+/// `ttimeoutlen` setting. Once the [deadline](Parser::deadline) passes, [`Parser::flush`]
+/// returns the buffered input as key events, so `ESC` becomes
+/// [`KeyCode::Esc`](crate::KeyCode::Esc) and `ESC [` becomes `Alt+[`.
+///
+/// # Unknown sequences
+///
+/// By default, a well-formed escape sequence that doesn't map to any known event is returned as
+/// a [`ParseError`] covering the entire sequence. If
+/// [reporting unknown sequences](Parser::report_unknown_sequences) is enabled, these are instead
+/// returned as [`Event::Unknown`] containing the raw bytes, so they can be logged or forwarded
+/// as-is. The bounds of each sequence are determined using the ECMA-48 grammar.
+///
+/// In this mode, string sequences such as OSC (`ESC ]`) and DCS (`ESC P`) are also recognized and
+/// kept until the string terminator arrives. These introducers are indistinguishable from
+/// Alt-modified keys, so an [escape timeout](Parser::escape_timeout) should be set to prevent
+/// something like `Alt+]` from waiting on more input indefinitely.
+///
+/// # Large pastes
+///
+/// By default, a bracketed paste is buffered until the end of the paste arrives, regardless of
+/// its size. Setting a [maximum paste length](Parser::max_paste_len) limits the amount of text
+/// that's buffered. Once a paste exceeds the limit, the text that was received so far is returned
+/// as an [`Event::Paste`] and the rest of the paste is discarded, unless
+/// [streaming](Parser::stream_pastes) is enabled, in which case the rest of the paste is returned
+/// in chunks as additional [`Event::Paste`] events.
+///
+/// # Terminal responses
+///
+/// Responses to queries sent to the terminal are returned as [`Event::Response`]. Cursor position
+/// reports for the first row (`CSI 1 ; column R`) are identical to the F3 key with modifiers, so
+/// these are only treated as a report if [`Parser::expect_cursor_position`] was called after
+/// sending the query.
+#[derive(Debug, Default, Clone)]
+pub struct Parser {
+    buffer: Vec<u8>,
+    // Length of the prefix of the buffer that's already known to be incomplete
+    scanned: usize,
+    escape_timeout: Option<Duration>,
+    pending_since: Option<Instant>,
+    // Length of the prefix of the buffer that should be treated as complete, even if it ends in
+    // an incomplete sequence
+    flush_until: Option<usize>,
+    report_unknown: bool,
+    // Number of cursor position reports that were requested but not received yet
+    expected_cursor_positions: usize,
+    mouse_encoding: MouseEncoding,
+    max_paste_len: Option<usize>,
+    stream_pastes: bool,
+    // Set while the rest of a paste that exceeded the maximum length is being received. The
+    // buffer holds the pasted text without the start of the paste.
+    in_paste: bool,
+}
+
+impl Parser {
+    /// Creates a new [`Parser`] with an empty buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the amount of time to wait for the rest of an escape sequence before treating the
+    /// buffered input as individual keys.
+    pub fn escape_timeout(mut self, timeout: Duration) -> Self {
+        self.escape_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets whether well-formed escape sequences that aren't recognized should be returned as
+    /// [`Event::Unknown`] instead of an error.
+    pub fn report_unknown_sequences(mut self, report: bool) -> Self {
+        self.report_unknown = report;
+        self
+    }
+
+    /// Sets the [`MouseEncoding`] that was enabled by the application. This is only needed for
+    /// encodings that have the same format as another encoding. [`MouseEncoding::Utf8`]
+    /// sequences look like [`MouseEncoding::X10`] sequences, and [`MouseEncoding::SgrPixels`]
+    /// sequences look like [`MouseEncoding::Sgr`] sequences. The other encodings are always
+    /// recognized.
+    ///
+    /// When using [`MouseEncoding::SgrPixels`], mouse events contain a
+    /// [pixel position](crate::MouseEvent::pixel_position) instead of a column and row.
+    pub fn mouse_encoding(mut self, mouse_encoding: MouseEncoding) -> Self {
+        self.mouse_encoding = mouse_encoding;
+        self
+    }
+
+    /// Sets the maximum number of bytes of pasted text that are buffered for a single paste. Any
+    /// text past this point is discarded, unless [streaming](Parser::stream_pastes) is enabled.
+    pub fn max_paste_len(mut self, max_paste_len: usize) -> Self {
+        self.max_paste_len = Some(max_paste_len);
+        self
+    }
+
+    /// Sets whether pastes longer than the [maximum paste length](Parser::max_paste_len) are
+    /// returned in chunks instead of being truncated. Each chunk is returned as a separate
+    /// [`Event::Paste`]. This has no effect if there's no maximum paste length.
+    pub fn stream_pastes(mut self, stream: bool) -> Self {
+        self.stream_pastes = stream;
+        self
+    }
+
+    /// Signals that a cursor position report was requested from the terminal, so the next
+    /// ambiguous sequence should be parsed as a
+    /// [`CursorPosition`](TerminalResponse::CursorPosition) instead of a key event. This should be
+    /// called once for each request that's sent.
+    pub fn expect_cursor_position(&mut self) {
+        self.expected_cursor_positions += 1;
+    }
+
+    /// Returns the time at which the pending input should be [flushed](Parser::flush) if no
+    /// more input arrives. Returns [`None`] if there's no escape timeout set or if there's no
+    /// pending input that's waiting on the timeout.
+    pub fn deadline(&self) -> Option<Instant> {
+        let timeout = self.escape_timeout?;
+        let pending_since = self.pending_since?;
+        if self.buffer.starts_with(b"\x1B")
+            && !self.buffer.starts_with(b"\x1B[200~")
+            && !self.in_paste
+        {
+            Some(pending_since + timeout)
+        } else {
+            None
+        }
+    }
+
+    /// Treats all of the pending input as complete and returns an iterator over the resulting
+    /// events. Any incomplete escape sequence is returned as the escape key or as an Alt-modified
+    /// key. Other input that was cut off, such as part of a UTF-8 character, is returned as
+    /// [`ParseError::IncompleteSequence`].
+    pub fn flush(&mut self) -> Events<'_> {
+        self.flush_until = Some(self.buffer.len());
+        Events { parser: self }
+    }
+
+    /// Adds the input to the internal buffer and returns an iterator over all of the complete
+    /// events. Each item contains the event along with the number of bytes that were consumed to
+    /// produce it.
+    ///
+    /// If the iterator is dropped before it's exhausted, the remaining events will be returned on
+    /// the next call.
+    ///
+    /// If the [deadline](Parser::deadline) for the pending input has already passed, the pending
+    /// input is [flushed](Parser::flush) before the new input is parsed.
+    pub fn parse(&mut self, input: &[u8]) -> Events<'_> {
+        if self
+            .deadline()
+            .is_some_and(|deadline| deadline <= Instant::now())
+        {
+            self.flush_until = Some(self.buffer.len());
+        }
+        self.buffer.extend_from_slice(input);
+        Events { parser: self }
+    }
+
+    /// Returns any bytes that have been received but not parsed into an event yet.
+    pub fn pending(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    /// Discards any buffered input.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.scanned = 0;
+        self.pending_since = None;
+        self.flush_until = None;
+        self.expected_cursor_positions = 0;
+        self.in_paste = false;
+    }
+
+    fn context(&self) -> ParseContext {
+        ParseContext {
+            expect_cursor_position: self.expected_cursor_positions > 0,
+            mouse_encoding: self.mouse_encoding,
+        }
+    }
+
+    fn next_event(&mut self) -> Option<Result<(Event, usize), ParseError>> {
+        let limit = self.flush_until.unwrap_or(self.buffer.len());
+        // Any input past the limit was received after the pending input timed out, so it
+        // shouldn't be treated as part of the same sequence.
+        let more_input = self.flush_until.is_none() && self.escape_timeout.is_some();
+        let context = self.context();
+        if let Some(max_len) = self.max_paste_len {
+            if self.in_paste || self.buffer[..limit].starts_with(b"\x1B[200~") {
+                return self.next_paste(limit, max_len);
+            }
+        }
+        // Terminal version reports are the only string sequences that are always recognized.
+        if (self.report_unknown || self.buffer[..limit].starts_with(b"\x1BP>|"))
+            && is_string_introducer(&self.buffer[..limit])
+        {
+            match sequence_bounds(&self.buffer[..limit], true) {
+                SequenceBounds::Complete(len) => {
+                    if let Ok(Some(event)) = parse_event_with(&self.buffer[..len], context) {
+                        return Some(Ok((event, self.consume(len))));
+                    }
+                    return Some(self.unknown_sequence(len));
+                }
+                SequenceBounds::Incomplete if self.flush_until.is_none() => {
+                    return self.wait(limit);
+                }
+                // The string was never terminated, so treat it as regular input
+                _ => {}
+            }
+        }
+        for end in self.scanned.max(1)..=limit {
+            let buffer = &self.buffer[..end];
+            // A lone escape could be the start of a longer sequence if there's more data after
+            // it.
+            if (end < limit || more_input) && matches!(buffer, b"\x1B" | b"\x1B\x1B") {
+                continue;
+            }
+            match parse_event_with(buffer, context) {
+                Ok(Some(event)) => return Some(Ok((event, self.consume(end)))),
+                // The sequence is complete, but the parser doesn't know how to handle it.
+                // Treating it as incomplete would cause the parser to wait forever.
+                Ok(None) if sequence_bounds(buffer, false) == SequenceBounds::Complete(end) => {
+                    return Some(self.unknown_sequence(end));
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    // Skip over the entire sequence so the remaining bytes aren't interpreted as
+                    // regular input.
+                    match sequence_bounds(&self.buffer[..limit], false) {
+                        SequenceBounds::Complete(len) if len >= end => {
+                            return Some(self.unknown_sequence(len));
+                        }
+                        SequenceBounds::Incomplete if self.flush_until.is_none() => {
+                            return self.wait(limit);
+                        }
+                        _ => {}
+                    }
+                    // The last byte wasn't a valid continuation of the sequence. If everything
+                    // before it was a valid event, return that and start over with the last byte.
+                    if let Ok(Some(event)) = parse_event_with(&buffer[..end - 1], context) {
+                        return Some(Ok((event, self.consume(end - 1))));
+                    }
+                    self.consume(end);
+                    return Some(Err(e));
+                }
+            }
+        }
+
+        if self.flush_until.is_some() {
+            return self.flush_incomplete(limit);
+        }
+        self.wait(limit)
+    }
+
+    fn next_paste(
+        &mut self,
+        limit: usize,
+        max_len: usize,
+    ) -> Option<Result<(Event, usize), ParseError>> {
+        // The start of the paste is only in the buffer if nothing has been returned for it yet
+        let start = if self.in_paste { 0 } else { 6 };
+        let text = &self.buffer[start..limit];
+        let end = text.windows(6).position(|w| w == b"\x1B[201~");
+        let discard = self.in_paste && !self.stream_pastes;
+        match end {
+            // The rest of a truncated paste is dropped, along with the empty chunk at the end of
+            // a streamed paste
+            Some(0) if self.in_paste => {
+                self.in_paste = false;
+                self.consume(6);
+                self.next_event()
+            }
+            Some(end) if discard => {
+                self.in_paste = false;
+                self.consume(end + 6);
+                self.next_event()
+            }
+            Some(end) if end <= max_len => {
+                self.in_paste = false;
+                let paste = String::from_utf8_lossy(&text[..end]).to_string();
+                Some(Ok((Event::Paste(paste), self.consume(start + end + 6))))
+            }
+            Some(end) => {
+                let len = char_boundary(text, max_len);
+                let paste = String::from_utf8_lossy(&text[..len]).to_string();
+                // Truncated pastes are complete, but streamed pastes continue with the next chunk
+                let consumed = if self.stream_pastes {
+                    self.in_paste = true;
+                    start + len
+                } else {
+                    start + end + 6
+                };
+                Some(Ok((Event::Paste(paste), self.consume(consumed))))
+            }
+            // The paste was never terminated, so treat everything that was received as the
+            // end of the paste
+            None if self.flush_until.is_some() => {
+                if discard || text.is_empty() && self.in_paste {
+                    self.in_paste = false;
+                    self.consume(limit);
+                    return self.next_event();
+                }
+                let len = char_boundary(text, max_len);
+                let paste = String::from_utf8_lossy(&text[..len]).to_string();
+                let consumed = if self.stream_pastes && len < text.len() {
+                    self.in_paste = true;
+                    start + len
+                } else {
+                    self.in_paste = false;
+                    limit
+                };
+                Some(Ok((Event::Paste(paste), self.consume(consumed))))
+            }
+            None => {
+                // The last few bytes could be the start of the terminator
+                let available = text.len().saturating_sub(5);
+                if discard {
+                    self.consume(available);
+                    self.wait(limit - available)
+                } else if available >= max_len.max(1) {
+                    let len = char_boundary(text, max_len);
+                    let paste = String::from_utf8_lossy(&text[..len]).to_string();
+                    let consumed = if self.stream_pastes {
+                        start + len
+                    } else {
+                        start + available
+                    };
+                    self.in_paste = true;
+                    Some(Ok((Event::Paste(paste), self.consume(consumed))))
+                } else {
+                    self.wait(limit)
+                }
+            }
+        }
+    }
+
+    fn wait(&mut self, limit: usize) -> Option<Result<(Event, usize), ParseError>> {
+        self.scanned = limit;
+        if limit > 0 && self.pending_since.is_none() {
+            self.pending_since = Some(Instant::now());
+        }
+        None
+    }
+
+    fn unknown_sequence(&mut self, len: usize) -> Result<(Event, usize), ParseError> {
+        let raw = self.buffer[..len].to_vec();
+        self.consume(len);
+        if self.report_unknown {
+            return Ok((Event::Unknown { raw }, len));
+        }
+        match parse_event_with(&raw, self.context()) {
+            Err(e) => Err(e),
+            _ => Err(ParseError::UnknownSequence { range: 0..len }),
+        }
+    }
+
+    fn flush_incomplete(&mut self, limit: usize) -> Option<Result<(Event, usize), ParseError>> {
+        if limit == 0 {
+            self.flush_until = None;
+            return None;
+        }
+        // An incomplete escape sequence is treated as the escape key being combined with the
+        // first character in the sequence.
+        if limit > 1 && self.buffer[0] == b'\x1B' && self.buffer[1].is_ascii() {
+            if let Ok(Some(Event::Key(mut key_event))) =
+                parse_event_with(&self.buffer[1..2], ParseContext::default())
+            {
+                key_event.modifiers |= KeyModifiers::ALT;
+                return Some(Ok((Event::Key(key_event), self.consume(2))));
+            }
+        }
+        // Only bytes that can never be part of a character are invalid. Anything else is input
+        // that was cut off, such as the first bytes of a character.
+        let error = match std::str::from_utf8(&self.buffer[..limit]) {
+            Err(e) if e.error_len().is_some() => ParseError::InvalidUtf8 {
+                range: e.valid_up_to()..limit,
+            },
+            _ => ParseError::IncompleteSequence { range: 0..limit },
+        };
+        self.consume(limit);
+        Some(Err(error))
+    }
+
+    fn consume(&mut self, len: usize) -> usize {
+        self.buffer.drain(..len);
+        self.scanned = 0;
+        self.pending_since = None;
+        self.flush_until = self
+            .flush_until
+            .map(|flush_until| flush_until.saturating_sub(len))
+            .filter(|flush_until| *flush_until > 0);
+        len
+    }
+}
+
+/// Iterator over the events parsed by [`Parser::parse`].
+#[derive(Debug)]
+pub struct Events<'a> {
+    parser: &'a mut Parser,
+}
+
+impl Iterator for Events<'_> {
+    type Item = Result<(Event, usize), ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let event = self.parser.next_event();
+        if let Some(Ok((Event::Response(TerminalResponse::CursorPosition { .. }), _))) = event {
+            self.parser.expected_cursor_positions =
+                self.parser.expected_cursor_positions.saturating_sub(1);
+        }
+        event
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SequenceBounds {
+    // The buffer doesn't start with an escape sequence that can be handled generically
+    NotSequence,
+    Incomplete,
+    // The length of the sequence
+    Complete(usize),
+}
+
+// Finds the length of a chunk of text that's at most `max_len` bytes without splitting a UTF-8
+// character. The chunk always contains at least one character if the text isn't empty.
+fn char_boundary(text: &[u8], max_len: usize) -> usize {
+    let is_continuation = |len: usize| text.get(len).is_some_and(|b| b & 0xC0 == 0x80);
+    let mut len = max_len.min(text.len());
+    while len > 0 && is_continuation(len) {
+        len -= 1;
+    }
+    if len == 0 && !text.is_empty() {
+        len = 1;
+        while is_continuation(len) {
+            len += 1;
+        }
+    }
+    len
+}
+
+fn is_string_introducer(buffer: &[u8]) -> bool {
+    // OSC, DCS, SOS, PM, and APC
+    matches!(buffer, [b'\x1B', b']' | b'P' | b'X' | b'^' | b'_', ..])
+}
+
+// Finds the end of the escape sequence at the start of the buffer using the ECMA-48 grammar.
+// CSI sequences are made up of any number of parameter bytes (0x30-0x3F) followed by any number of
+// intermediate bytes (0x20-0x2F) and a single final byte (0x40-0x7E). String sequences are
+// terminated by ST (ESC \), or by BEL in the case of OSC.
+fn sequence_bounds(buffer: &[u8], strings: bool) -> SequenceBounds {
+    // These sequences contain extra data after the final byte
+    if buffer.starts_with(b"\x1B[200~")
+        || buffer.starts_with(b"\x1B[M")
+        || buffer.starts_with(b"\x1B[[")
+    {
+        return SequenceBounds::NotSequence;
+    }
+    match buffer {
+        [b'\x1B'] => SequenceBounds::Incomplete,
+        // SS3 is normally followed by a single character, but some terminals send modifiers as
+        // parameters
+        [b'\x1B', b'[' | b'O', body @ ..] => {
+            let params = body
+                .iter()
+                .take_while(|b| (0x30..=0x3F).contains(*b))
+                .count();
+            let intermediates = body[params..]
+                .iter()
+                .take_while(|b| (0x20..=0x2F).contains(*b))
+                .count();
+            match body.get(params + intermediates) {
+                Some(0x40..=0x7E) => SequenceBounds::Complete(params + intermediates + 3),
+                Some(_) => SequenceBounds::NotSequence,
+                None => SequenceBounds::Incomplete,
+            }
+        }
+        [_, body @ ..] if strings && is_string_introducer(buffer) => {
+            let st = body.windows(2).position(|w| w == b"\x1B\\");
+            let bel = if buffer[1] == b']' {
+                body.iter().position(|b| *b == b'\x07')
+            } else {
+                None
+            };
+            match (st, bel) {
+                (Some(st), Some(bel)) if bel < st => SequenceBounds::Complete(bel + 2),
+                (Some(st), _) => SequenceBounds::Complete(st + 3),
+                (None, Some(bel)) => SequenceBounds::Complete(bel + 2),
+                (None, None) => SequenceBounds::Incomplete,
+            }
+        }
+        _ => SequenceBounds::NotSequence,
+    }
+}
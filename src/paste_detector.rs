@@ -0,0 +1,182 @@
+use std::mem;
+use std::time::{Duration, Instant};
+
+use crate::{Event, KeyCode, KeyEventKind, KeyModifiers};
+
+/// Detects pastes in terminals that don't support bracketed paste or have it disabled.
+///
+/// Without bracketed paste, pasted text is indistinguishable from typed text, so it's parsed into
+/// a burst of key events. The detector merges runs of printable keys that arrive too quickly to
+/// have been typed into a single [`Event::Paste`]. All other events are passed through unchanged.
+/// It works with events from any source, such as the [`Parser`](crate::Parser) or one of the
+/// supported backends.
+///
+/// Events should be passed to [`PasteDetector::process`] in the batches they were received in,
+/// such as all of the events parsed from a single read. By default, only runs within a single
+/// batch are merged. If a [maximum interval](PasteDetector::max_interval) is set, runs that span
+/// multiple batches are also merged, as long as each batch arrives within the interval of the
+/// previous one. In this case, printable keys at the end of a batch are held until the next batch
+/// arrives or the [deadline](PasteDetector::deadline) passes, after which
+/// [`PasteDetector::flush`] should be called.
+///
+/// Enter and Tab keys are included in the pasted text as `\n` and `\t`. Key repeats end the run,
+/// since they're only sent for keys that are being held down.
+///
+/// ```
+/// use terminput::{Event, KeyCode, Parser, PasteDetector};
+///
+/// let mut parser = Parser::new();
+/// let mut detector = PasteDetector::new();
+///
+/// let events: Vec<_> = parser.parse(b"a").map(|e| e.unwrap().0).collect();
+/// assert_eq!(
+///     detector.process(events),
+///     vec![Event::Key(KeyCode::Char('a').into())]
+/// );
+///
+/// let events: Vec<_> = parser
+///     .parse(b"hello\rworld")
+///     .map(|e| e.unwrap().0)
+///     .collect();
+/// assert_eq!(
+///     detector.process(events),
+///     vec![Event::Paste("hello\nworld".to_string())]
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct PasteDetector {
+    min_len: usize,
+    max_interval: Duration,
+    // Printable key events that could be part of a paste
+    pending: Vec<Event>,
+    // The text represented by the pending events
+    text: String,
+    last_batch: Option<Instant>,
+}
+
+impl Default for PasteDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PasteDetector {
+    /// Creates a new [`PasteDetector`] that merges runs of at least 4 characters within a
+    /// single batch of events.
+    pub fn new() -> Self {
+        Self {
+            min_len: 4,
+            max_interval: Duration::ZERO,
+            pending: Vec::new(),
+            text: String::new(),
+            last_batch: None,
+        }
+    }
+
+    /// Sets the minimum number of characters in a run of printable keys for it to be treated as a
+    /// paste.
+    pub fn min_len(mut self, min_len: usize) -> Self {
+        self.min_len = min_len;
+        self
+    }
+
+    /// Sets the maximum amount of time between batches of events for runs of printable keys to be
+    /// merged across them.
+    pub fn max_interval(mut self, max_interval: Duration) -> Self {
+        self.max_interval = max_interval;
+        self
+    }
+
+    /// Returns the time at which the held events should be [flushed](PasteDetector::flush) if no
+    /// more events arrive. Returns [`None`] if no events are being held.
+    pub fn deadline(&self) -> Option<Instant> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        self.last_batch
+            .map(|last_batch| last_batch + self.max_interval)
+    }
+
+    /// Processes a batch of events and returns the events that are ready to be handled. Runs of
+    /// printable keys are merged into a single [`Event::Paste`] if they're long enough.
+    ///
+    /// If the [deadline](PasteDetector::deadline) for the held events has already passed, they're
+    /// returned before the new events are processed.
+    pub fn process<I>(&mut self, events: I) -> Vec<Event>
+    where
+        I: IntoIterator<Item = Event>,
+    {
+        let now = Instant::now();
+        let mut processed = Vec::new();
+        if self.deadline().is_some_and(|deadline| deadline < now) {
+            self.finish_run(&mut processed);
+        }
+        for event in events {
+            match paste_text(&event) {
+                Some(text) => {
+                    if let Some(c) = text {
+                        self.text.push(c);
+                    }
+                    self.pending.push(event);
+                }
+                None => {
+                    self.finish_run(&mut processed);
+                    processed.push(event);
+                }
+            }
+        }
+        if self.max_interval.is_zero() {
+            self.finish_run(&mut processed);
+        } else {
+            self.last_batch = Some(now);
+        }
+        processed
+    }
+
+    /// Returns all of the held events, treating the current run of printable keys as complete.
+    pub fn flush(&mut self) -> Vec<Event> {
+        let mut processed = Vec::new();
+        self.finish_run(&mut processed);
+        processed
+    }
+
+    /// Returns the events that are being held until the run of printable keys is complete.
+    pub fn pending(&self) -> &[Event] {
+        &self.pending
+    }
+
+    fn finish_run(&mut self, processed: &mut Vec<Event>) {
+        let text_len = self.text.chars().count();
+        if text_len >= self.min_len && text_len > 0 {
+            self.pending.clear();
+            processed.push(Event::Paste(mem::take(&mut self.text)));
+        } else {
+            self.text.clear();
+            processed.append(&mut self.pending);
+        }
+    }
+}
+
+// Returns the character that the event contributes to a paste. Key releases can appear in the
+// middle of a paste, but don't add any text. Returns `None` if the event can't be part of a paste,
+// including key repeats, which only come from keys being held down.
+fn paste_text(event: &Event) -> Option<Option<char>> {
+    let Event::Key(key_event) = event else {
+        return None;
+    };
+    let key_event = key_event.normalize_case();
+    if !(key_event.modifiers - KeyModifiers::SHIFT).is_empty() {
+        return None;
+    }
+    let c = match key_event.code {
+        KeyCode::Char(c) if !c.is_control() => c,
+        KeyCode::Enter => '\n',
+        KeyCode::Tab => '\t',
+        _ => return None,
+    };
+    match key_event.kind {
+        KeyEventKind::Press => Some(Some(c)),
+        KeyEventKind::Release => Some(None),
+        KeyEventKind::Repeat => None,
+    }
+}
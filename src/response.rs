@@ -0,0 +1,58 @@
+use crate::KittyFlags;
+
+/// A response to a query that was sent to the terminal.
+///
+/// Terminals report these using the same input stream as keyboard and mouse events, so they're
+/// returned as [`Event::Response`](crate::Event::Response).
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Hash)]
+pub enum TerminalResponse {
+    /// Primary device attributes (DA1), sent in response to `CSI c`.
+    ///
+    /// Reported as `CSI ? Ps ; ... c`.
+    PrimaryDeviceAttributes {
+        /// The first parameter, which represents the conformance level of the terminal (e.g.
+        /// `62` for VT220).
+        conformance_level: u16,
+        /// Any remaining parameters, which represent the features supported by the terminal.
+        features: Vec<u16>,
+    },
+    /// Secondary device attributes (DA2), sent in response to `CSI > c`.
+    ///
+    /// Reported as `CSI > Pp ; Pv ; Pc c`.
+    SecondaryDeviceAttributes {
+        /// Identifies the type of terminal.
+        terminal_type: u16,
+        /// The firmware version of the terminal.
+        firmware_version: u32,
+        /// The ROM cartridge registration number. This is always zero for modern terminals.
+        rom_cartridge: u16,
+    },
+    /// Cursor position report (CPR), sent in response to `CSI 6 n`.
+    ///
+    /// Reported as `CSI row ; column R`. Unlike the report, these values are 0-based.
+    ///
+    /// Reports for the first row are indistinguishable from the F3 key with modifiers,
+    /// so they're only parsed as a cursor position if the [`Parser`](crate::Parser) is
+    /// [expecting a report](crate::Parser::expect_cursor_position).
+    CursorPosition {
+        /// The row that the cursor is on.
+        row: u16,
+        /// The column that the cursor is on.
+        column: u16,
+    },
+    /// The currently active Kitty keyboard enhancement flags, sent in response to `CSI ? u`.
+    ///
+    /// Reported as `CSI ? flags u`.
+    KittyKeyboardFlags {
+        /// The active flags.
+        flags: KittyFlags,
+    },
+    /// The name and version of the terminal (XTVERSION), sent in response to `CSI > q`.
+    ///
+    /// Reported as `DCS > | text ST`.
+    TerminalVersion {
+        /// The text reported by the terminal. This usually contains the name of the terminal
+        /// followed by the version.
+        version: String,
+    },
+}
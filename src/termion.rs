@@ -1,6 +1,6 @@
 use crate::{
-    Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers, MouseButton, MouseEvent,
-    MouseEventKind, UnsupportedEvent,
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+    UnsupportedEvent,
 };
 
 impl TryFrom<termion::event::Event> for Event {
@@ -22,9 +22,12 @@ impl TryFrom<Event> for termion::event::Event {
         Ok(match value {
             Event::Key(key_event) => Self::Key(key_event.try_into()?),
             Event::Mouse(mouse_event) => Self::Mouse(mouse_event.try_into()?),
-            Event::FocusGained | Event::FocusLost | Event::Paste(_) | Event::Resize { .. } => {
-                Err(UnsupportedEvent(format!("{value:?}")))?
-            }
+            Event::Unknown { raw } => Self::Unsupported(raw),
+            Event::FocusGained
+            | Event::FocusLost
+            | Event::Paste(_)
+            | Event::Resize { .. }
+            | Event::Response(_) => Err(UnsupportedEvent(format!("{value:?}")))?,
         })
     }
 }
@@ -34,228 +37,59 @@ impl TryFrom<termion::event::Key> for KeyEvent {
 
     fn try_from(value: termion::event::Key) -> Result<Self, Self::Error> {
         Ok(match value {
-            termion::event::Key::Backspace => Self {
-                code: KeyCode::Backspace,
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::Left => Self {
-                code: KeyCode::Left,
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::ShiftLeft => Self {
-                code: KeyCode::Left,
-                modifiers: KeyModifiers::SHIFT,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::AltLeft => Self {
-                code: KeyCode::Left,
-                modifiers: KeyModifiers::ALT,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::CtrlLeft => Self {
-                code: KeyCode::Left,
-                modifiers: KeyModifiers::CTRL,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::Right => Self {
-                code: KeyCode::Right,
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::ShiftRight => Self {
-                code: KeyCode::Right,
-                modifiers: KeyModifiers::SHIFT,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::AltRight => Self {
-                code: KeyCode::Right,
-                modifiers: KeyModifiers::ALT,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::CtrlRight => Self {
-                code: KeyCode::Right,
-                modifiers: KeyModifiers::CTRL,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::Up => Self {
-                code: KeyCode::Up,
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::ShiftUp => Self {
-                code: KeyCode::Up,
-                modifiers: KeyModifiers::SHIFT,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::AltUp => Self {
-                code: KeyCode::Up,
-                modifiers: KeyModifiers::ALT,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::CtrlUp => Self {
-                code: KeyCode::Up,
-                modifiers: KeyModifiers::CTRL,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::Down => Self {
-                code: KeyCode::Down,
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::ShiftDown => Self {
-                code: KeyCode::Down,
-                modifiers: KeyModifiers::SHIFT,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::AltDown => Self {
-                code: KeyCode::Down,
-                modifiers: KeyModifiers::ALT,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::CtrlDown => Self {
-                code: KeyCode::Down,
-                modifiers: KeyModifiers::CTRL,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::Home => Self {
-                code: KeyCode::Home,
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::CtrlHome => Self {
-                code: KeyCode::Home,
-                modifiers: KeyModifiers::CTRL,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::End => Self {
-                code: KeyCode::End,
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::CtrlEnd => Self {
-                code: KeyCode::End,
-                modifiers: KeyModifiers::CTRL,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::PageUp => Self {
-                code: KeyCode::PageUp,
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::PageDown => Self {
-                code: KeyCode::PageDown,
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::BackTab => Self {
-                code: KeyCode::Tab,
-                modifiers: KeyModifiers::SHIFT,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::Delete => Self {
-                code: KeyCode::Delete,
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::Insert => Self {
-                code: KeyCode::Insert,
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::F(f) => Self {
-                code: KeyCode::F(f),
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::Char('\n') => Self {
-                code: KeyCode::Enter,
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::Char('\t') => Self {
-                code: KeyCode::Tab,
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::Char(c) => Self {
-                code: KeyCode::Char(c),
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::Alt('\n') => Self {
-                code: KeyCode::Enter,
-                modifiers: KeyModifiers::ALT,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::Alt('\t') => Self {
-                code: KeyCode::Tab,
-                modifiers: KeyModifiers::ALT,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::Alt(c) => Self {
-                code: KeyCode::Char(c),
-                modifiers: KeyModifiers::ALT,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::Ctrl('\n') => Self {
-                code: KeyCode::Enter,
-                modifiers: KeyModifiers::CTRL,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::Ctrl('\t') => Self {
-                code: KeyCode::Tab,
-                modifiers: KeyModifiers::CTRL,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::Ctrl(c) => Self {
-                code: KeyCode::Char(c),
-                modifiers: KeyModifiers::CTRL,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
-            termion::event::Key::Esc => Self {
-                code: KeyCode::Esc,
-                modifiers: KeyModifiers::NONE,
-                kind: KeyEventKind::Press,
-                state: KeyEventState::empty(),
-            },
+            termion::event::Key::Backspace => Self::new(KeyCode::Backspace),
+            termion::event::Key::Left => Self::new(KeyCode::Left),
+            termion::event::Key::ShiftLeft => {
+                Self::new(KeyCode::Left).modifiers(KeyModifiers::SHIFT)
+            }
+            termion::event::Key::AltLeft => Self::new(KeyCode::Left).modifiers(KeyModifiers::ALT),
+            termion::event::Key::CtrlLeft => Self::new(KeyCode::Left).modifiers(KeyModifiers::CTRL),
+            termion::event::Key::Right => Self::new(KeyCode::Right),
+            termion::event::Key::ShiftRight => {
+                Self::new(KeyCode::Right).modifiers(KeyModifiers::SHIFT)
+            }
+            termion::event::Key::AltRight => Self::new(KeyCode::Right).modifiers(KeyModifiers::ALT),
+            termion::event::Key::CtrlRight => {
+                Self::new(KeyCode::Right).modifiers(KeyModifiers::CTRL)
+            }
+            termion::event::Key::Up => Self::new(KeyCode::Up),
+            termion::event::Key::ShiftUp => Self::new(KeyCode::Up).modifiers(KeyModifiers::SHIFT),
+            termion::event::Key::AltUp => Self::new(KeyCode::Up).modifiers(KeyModifiers::ALT),
+            termion::event::Key::CtrlUp => Self::new(KeyCode::Up).modifiers(KeyModifiers::CTRL),
+            termion::event::Key::Down => Self::new(KeyCode::Down),
+            termion::event::Key::ShiftDown => {
+                Self::new(KeyCode::Down).modifiers(KeyModifiers::SHIFT)
+            }
+            termion::event::Key::AltDown => Self::new(KeyCode::Down).modifiers(KeyModifiers::ALT),
+            termion::event::Key::CtrlDown => Self::new(KeyCode::Down).modifiers(KeyModifiers::CTRL),
+            termion::event::Key::Home => Self::new(KeyCode::Home),
+            termion::event::Key::CtrlHome => Self::new(KeyCode::Home).modifiers(KeyModifiers::CTRL),
+            termion::event::Key::End => Self::new(KeyCode::End),
+            termion::event::Key::CtrlEnd => Self::new(KeyCode::End).modifiers(KeyModifiers::CTRL),
+            termion::event::Key::PageUp => Self::new(KeyCode::PageUp),
+            termion::event::Key::PageDown => Self::new(KeyCode::PageDown),
+            termion::event::Key::BackTab => Self::new(KeyCode::Tab).modifiers(KeyModifiers::SHIFT),
+            termion::event::Key::Delete => Self::new(KeyCode::Delete),
+            termion::event::Key::Insert => Self::new(KeyCode::Insert),
+            termion::event::Key::F(f) => Self::new(KeyCode::F(f)),
+            termion::event::Key::Char('\n') => Self::new(KeyCode::Enter),
+            termion::event::Key::Char('\t') => Self::new(KeyCode::Tab),
+            termion::event::Key::Char(c) => Self::new(KeyCode::Char(c)),
+            termion::event::Key::Alt('\n') => {
+                Self::new(KeyCode::Enter).modifiers(KeyModifiers::ALT)
+            }
+            termion::event::Key::Alt('\t') => Self::new(KeyCode::Tab).modifiers(KeyModifiers::ALT),
+            termion::event::Key::Alt(c) => Self::new(KeyCode::Char(c)).modifiers(KeyModifiers::ALT),
+            termion::event::Key::Ctrl('\n') => {
+                Self::new(KeyCode::Enter).modifiers(KeyModifiers::CTRL)
+            }
+            termion::event::Key::Ctrl('\t') => {
+                Self::new(KeyCode::Tab).modifiers(KeyModifiers::CTRL)
+            }
+            termion::event::Key::Ctrl(c) => {
+                Self::new(KeyCode::Char(c)).modifiers(KeyModifiers::CTRL)
+            }
+            termion::event::Key::Esc => Self::new(KeyCode::Esc),
             _ => Err(UnsupportedEvent(format!("{value:?}")))?,
         })
     }
@@ -340,6 +174,7 @@ impl TryFrom<termion::event::MouseEvent> for MouseEvent {
                     row: row - 1,
                     column: column - 1,
                     modifiers: KeyModifiers::NONE,
+                    pixel_position: None,
                 }
             }
             termion::event::MouseEvent::Press(termion::event::MouseButton::Right, column, row) => {
@@ -348,6 +183,7 @@ impl TryFrom<termion::event::MouseEvent> for MouseEvent {
                     row: row - 1,
                     column: column - 1,
                     modifiers: KeyModifiers::NONE,
+                    pixel_position: None,
                 }
             }
             termion::event::MouseEvent::Press(termion::event::MouseButton::Middle, column, row) => {
@@ -356,6 +192,7 @@ impl TryFrom<termion::event::MouseEvent> for MouseEvent {
                     row: row - 1,
                     column: column - 1,
                     modifiers: KeyModifiers::NONE,
+                    pixel_position: None,
                 }
             }
             termion::event::MouseEvent::Press(
@@ -367,6 +204,7 @@ impl TryFrom<termion::event::MouseEvent> for MouseEvent {
                 row: row - 1,
                 column: column - 1,
                 modifiers: KeyModifiers::NONE,
+                pixel_position: None,
             },
             termion::event::MouseEvent::Press(
                 termion::event::MouseButton::WheelUp,
@@ -377,6 +215,7 @@ impl TryFrom<termion::event::MouseEvent> for MouseEvent {
                 row: row - 1,
                 column: column - 1,
                 modifiers: KeyModifiers::NONE,
+                pixel_position: None,
             },
             termion::event::MouseEvent::Press(
                 termion::event::MouseButton::WheelLeft,
@@ -387,6 +226,7 @@ impl TryFrom<termion::event::MouseEvent> for MouseEvent {
                 row: row - 1,
                 column: column - 1,
                 modifiers: KeyModifiers::NONE,
+                pixel_position: None,
             },
             termion::event::MouseEvent::Press(
                 termion::event::MouseButton::WheelRight,
@@ -397,18 +237,21 @@ impl TryFrom<termion::event::MouseEvent> for MouseEvent {
                 row: row - 1,
                 column: column - 1,
                 modifiers: KeyModifiers::NONE,
+                pixel_position: None,
             },
             termion::event::MouseEvent::Release(column, row) => Self {
                 kind: MouseEventKind::Up(MouseButton::Unknown),
                 row: row - 1,
                 column: column - 1,
                 modifiers: KeyModifiers::NONE,
+                pixel_position: None,
             },
             termion::event::MouseEvent::Hold(column, row) => Self {
                 kind: MouseEventKind::Drag(MouseButton::Unknown),
                 row: row - 1,
                 column: column - 1,
                 modifiers: KeyModifiers::NONE,
+                pixel_position: None,
             },
         })
     }
@@ -430,9 +273,12 @@ impl TryFrom<MouseEvent> for termion::event::MouseEvent {
             MouseEventKind::Down(MouseButton::Middle) => {
                 Self::Press(termion::event::MouseButton::Middle, column, row)
             }
-            val @ MouseEventKind::Down(MouseButton::Unknown) => {
-                Err(UnsupportedEvent(format!("{val:?}")))?
-            }
+            val @ MouseEventKind::Down(
+                MouseButton::Unknown
+                | MouseButton::Back
+                | MouseButton::Forward
+                | MouseButton::Other(_),
+            ) => Err(UnsupportedEvent(format!("{val:?}")))?,
             MouseEventKind::Up(_) => Self::Release(column, row),
             MouseEventKind::Drag(_) => Self::Hold(column, row),
             val @ MouseEventKind::Moved => Err(UnsupportedEvent(format!("{val:?}")))?,
@@ -1,6 +1,6 @@
 use crate::{
-    Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers, MediaKeyCode,
-    ModifierDirection, ModifierKeyCode, MouseButton, MouseEvent, MouseEventKind, UnsupportedEvent,
+    Event, KeyCode, KeyEvent, KeyEventState, KeyModifiers, MediaKeyCode, ModifierDirection,
+    ModifierKeyCode, MouseButton, MouseEvent, MouseEventKind, PixelPosition, UnsupportedEvent,
 };
 
 impl TryFrom<termwiz::input::InputEvent> for Event {
@@ -13,11 +13,13 @@ impl TryFrom<termwiz::input::InputEvent> for Event {
             termwiz::input::InputEvent::Resized { cols, rows } => Self::Resize {
                 cols: cols as u32,
                 rows: rows as u32,
+                pixel_size: None,
             },
             termwiz::input::InputEvent::Paste(val) => Self::Paste(val),
-            termwiz::input::InputEvent::PixelMouse(_) | termwiz::input::InputEvent::Wake => {
-                Err(UnsupportedEvent(format!("{value:?}")))?
+            termwiz::input::InputEvent::PixelMouse(mouse_event) => {
+                Self::Mouse(mouse_event.try_into()?)
             }
+            termwiz::input::InputEvent::Wake => Err(UnsupportedEvent(format!("{value:?}")))?,
         })
     }
 }
@@ -28,13 +30,18 @@ impl TryFrom<Event> for termwiz::input::InputEvent {
     fn try_from(value: Event) -> Result<Self, Self::Error> {
         Ok(match value {
             Event::Key(key_event) => Self::Key(key_event.try_into()?),
+            Event::Mouse(mouse_event) if mouse_event.pixel_position.is_some() => {
+                Self::PixelMouse(mouse_event.try_into()?)
+            }
             Event::Mouse(mouse_event) => Self::Mouse(mouse_event.try_into()?),
             Event::Paste(val) => Self::Paste(val),
-            Event::Resize { cols, rows } => Self::Resized {
+            Event::Resize { cols, rows, .. } => Self::Resized {
                 cols: cols as usize,
                 rows: rows as usize,
             },
-            Event::FocusGained | Event::FocusLost => Err(UnsupportedEvent(format!("{value:?}")))?,
+            Event::FocusGained | Event::FocusLost | Event::Response(_) | Event::Unknown { .. } => {
+                Err(UnsupportedEvent(format!("{value:?}")))?
+            }
         })
     }
 }
@@ -184,12 +191,9 @@ impl TryFrom<termwiz::input::KeyEvent> for KeyEvent {
             termwiz::input::KeyCode::KeyPadBegin => (KeyCode::KeypadBegin, KeyEventState::KEYPAD),
             _ => Err(UnsupportedEvent(format!("{value:?}")))?,
         };
-        Ok(Self {
-            code,
-            modifiers: value.modifiers.try_into()?,
-            kind: KeyEventKind::Press,
-            state,
-        })
+        Ok(Self::new(code)
+            .modifiers(value.modifiers.try_into()?)
+            .state(state))
     }
 }
 
@@ -368,6 +372,7 @@ impl TryFrom<termwiz::input::MouseEvent> for MouseEvent {
                 column: value.x - 1,
                 row: value.y - 1,
                 modifiers: value.modifiers.try_into()?,
+                pixel_position: None,
             });
         }
         if value
@@ -379,6 +384,7 @@ impl TryFrom<termwiz::input::MouseEvent> for MouseEvent {
                 column: value.x - 1,
                 row: value.y - 1,
                 modifiers: value.modifiers.try_into()?,
+                pixel_position: None,
             });
         }
         if value
@@ -390,6 +396,7 @@ impl TryFrom<termwiz::input::MouseEvent> for MouseEvent {
                 column: value.x - 1,
                 row: value.y - 1,
                 modifiers: value.modifiers.try_into()?,
+                pixel_position: None,
             });
         }
 
@@ -401,6 +408,7 @@ impl TryFrom<termwiz::input::MouseEvent> for MouseEvent {
                 column: value.x - 1,
                 row: value.y - 1,
                 modifiers: value.modifiers.try_into()?,
+                pixel_position: None,
             });
         }
         if value
@@ -412,6 +420,7 @@ impl TryFrom<termwiz::input::MouseEvent> for MouseEvent {
                 column: value.x - 1,
                 row: value.y - 1,
                 modifiers: value.modifiers.try_into()?,
+                pixel_position: None,
             });
         }
         if value.mouse_buttons.contains(
@@ -422,6 +431,7 @@ impl TryFrom<termwiz::input::MouseEvent> for MouseEvent {
                 column: value.x - 1,
                 row: value.y - 1,
                 modifiers: value.modifiers.try_into()?,
+                pixel_position: None,
             });
         }
         if value
@@ -433,6 +443,7 @@ impl TryFrom<termwiz::input::MouseEvent> for MouseEvent {
                 column: value.x - 1,
                 row: value.y - 1,
                 modifiers: value.modifiers.try_into()?,
+                pixel_position: None,
             });
         }
         if value.mouse_buttons == termwiz::input::MouseButtons::NONE {
@@ -441,6 +452,7 @@ impl TryFrom<termwiz::input::MouseEvent> for MouseEvent {
                 column: value.x - 1,
                 row: value.y - 1,
                 modifiers: value.modifiers.try_into()?,
+                pixel_position: None,
             });
         }
 
@@ -448,6 +460,47 @@ impl TryFrom<termwiz::input::MouseEvent> for MouseEvent {
     }
 }
 
+impl TryFrom<termwiz::input::PixelMouseEvent> for MouseEvent {
+    type Error = UnsupportedEvent;
+
+    fn try_from(value: termwiz::input::PixelMouseEvent) -> Result<Self, Self::Error> {
+        // Pixel events don't contain a cell position
+        let mouse_event: Self = termwiz::input::MouseEvent {
+            x: 1,
+            y: 1,
+            mouse_buttons: value.mouse_buttons,
+            modifiers: value.modifiers,
+        }
+        .try_into()?;
+        Ok(Self {
+            column: 0,
+            row: 0,
+            pixel_position: Some(PixelPosition {
+                x: value.x_pixels.saturating_sub(1),
+                y: value.y_pixels.saturating_sub(1),
+            }),
+            ..mouse_event
+        })
+    }
+}
+
+impl TryFrom<MouseEvent> for termwiz::input::PixelMouseEvent {
+    type Error = UnsupportedEvent;
+
+    fn try_from(value: MouseEvent) -> Result<Self, Self::Error> {
+        let Some(pixel_position) = value.pixel_position else {
+            return Err(UnsupportedEvent(format!("{value:?}")));
+        };
+        let mouse_event: termwiz::input::MouseEvent = value.try_into()?;
+        Ok(Self {
+            x_pixels: pixel_position.x.saturating_add(1),
+            y_pixels: pixel_position.y.saturating_add(1),
+            mouse_buttons: mouse_event.mouse_buttons,
+            modifiers: mouse_event.modifiers,
+        })
+    }
+}
+
 impl TryFrom<MouseEvent> for termwiz::input::MouseEvent {
     type Error = UnsupportedEvent;
 
@@ -471,9 +524,11 @@ impl TryFrom<MouseEvent> for termwiz::input::MouseEvent {
                 y: value.row + 1,
                 modifiers: value.modifiers.try_into()?,
             },
-            MouseEventKind::Up(_) | MouseEventKind::Drag(_) => {
-                Err(UnsupportedEvent(format!("{value:?}")))?
-            }
+            MouseEventKind::Down(
+                MouseButton::Back | MouseButton::Forward | MouseButton::Other(_),
+            )
+            | MouseEventKind::Up(_)
+            | MouseEventKind::Drag(_) => Err(UnsupportedEvent(format!("{value:?}")))?,
             MouseEventKind::Moved => Self {
                 mouse_buttons: termwiz::input::MouseButtons::NONE,
                 x: value.column + 1,
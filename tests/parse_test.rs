@@ -1,7 +1,12 @@
+use std::time::Duration;
+
 use terminput::{
-    Encoding, Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers, KittyFlags,
-    MediaKeyCode, ModifierDirection, ModifierKeyCode, MouseButton, MouseEvent, MouseEventKind,
-    parse_event,
+    EmacsNotation, Encoding, Event, KeyCode, KeyEvent, KeyEventKind, KeyEventState, KeyModifiers,
+    KeyPattern, KeyText, Keymap, KeymapLayer, KeymapMatch, KittyFlags, KittyFlagsCommand,
+    KittyFlagsMode, MediaKeyCode, ModeEncoder, ModifierDirection, ModifierKeyCode, MouseButton,
+    MouseEncoding, MouseEvent, MouseEventKind, MouseTracking, NotationError, ParseError, Parser,
+    PasteDetector, PasteSanitization, PixelPosition, PixelSize, TerminalResponse, TmuxNotation,
+    VimNotation, XtermOptions, parse_event,
 };
 
 #[test]
@@ -1567,6 +1572,7 @@ fn test_parse_event_subsequent_calls() {
             column: 29,
             row: 39,
             modifiers: KeyModifiers::empty(),
+            pixel_position: None,
         }))
     );
 
@@ -1578,6 +1584,7 @@ fn test_parse_event_subsequent_calls() {
             column: 63,
             row: 79,
             modifiers: KeyModifiers::CTRL,
+            pixel_position: None,
         }))
     );
 
@@ -1589,6 +1596,7 @@ fn test_parse_event_subsequent_calls() {
             column: 19,
             row: 9,
             modifiers: KeyModifiers::empty(),
+            pixel_position: None,
         }))
     );
 
@@ -1751,6 +1759,7 @@ fn test_parse_csi_rxvt_mouse() {
             column: 29,
             row: 39,
             modifiers: KeyModifiers::empty(),
+            pixel_position: None,
         }))
     );
 }
@@ -1764,6 +1773,7 @@ fn test_parse_csi_normal_mouse() {
             column: 63,
             row: 79,
             modifiers: KeyModifiers::CTRL,
+            pixel_position: None,
         }))
     );
 }
@@ -1776,7 +1786,8 @@ fn test_parse_csi_sgr_mouse() {
             kind: MouseEventKind::Moved,
             column: 19,
             row: 9,
-            modifiers: KeyModifiers::empty()
+            modifiers: KeyModifiers::empty(),
+            pixel_position: None,
         }))
     );
     let mut buf = [0; 16];
@@ -1785,6 +1796,7 @@ fn test_parse_csi_sgr_mouse() {
         column: 19,
         row: 9,
         modifiers: KeyModifiers::empty(),
+        pixel_position: None,
     })
     .encode(&mut buf, Encoding::Xterm)
     .unwrap();
@@ -1795,7 +1807,8 @@ fn test_parse_csi_sgr_mouse() {
             kind: MouseEventKind::Moved,
             column: 19,
             row: 9,
-            modifiers: KeyModifiers::SHIFT
+            modifiers: KeyModifiers::SHIFT,
+            pixel_position: None,
         }))
     );
     let mut buf = [0; 16];
@@ -1804,6 +1817,7 @@ fn test_parse_csi_sgr_mouse() {
         column: 19,
         row: 9,
         modifiers: KeyModifiers::SHIFT,
+        pixel_position: None,
     })
     .encode(&mut buf, Encoding::Xterm)
     .unwrap();
@@ -1816,6 +1830,7 @@ fn test_parse_csi_sgr_mouse() {
             column: 19,
             row: 9,
             modifiers: KeyModifiers::empty(),
+            pixel_position: None,
         }))
     );
     assert_eq!(
@@ -1825,6 +1840,7 @@ fn test_parse_csi_sgr_mouse() {
             column: 19,
             row: 9,
             modifiers: KeyModifiers::empty(),
+            pixel_position: None,
         }))
     );
     let mut buf = [0; 16];
@@ -1833,6 +1849,7 @@ fn test_parse_csi_sgr_mouse() {
         column: 19,
         row: 9,
         modifiers: KeyModifiers::empty(),
+        pixel_position: None,
     })
     .encode(&mut buf, Encoding::Xterm)
     .unwrap();
@@ -1845,6 +1862,7 @@ fn test_parse_csi_sgr_mouse() {
             column: 19,
             row: 9,
             modifiers: KeyModifiers::empty(),
+            pixel_position: None,
         }))
     );
     assert_eq!(
@@ -1854,6 +1872,7 @@ fn test_parse_csi_sgr_mouse() {
             column: 19,
             row: 9,
             modifiers: KeyModifiers::empty(),
+            pixel_position: None,
         }))
     );
     let mut buf = [0; 16];
@@ -1862,6 +1881,7 @@ fn test_parse_csi_sgr_mouse() {
         column: 19,
         row: 9,
         modifiers: KeyModifiers::empty(),
+        pixel_position: None,
     })
     .encode(&mut buf, Encoding::Xterm)
     .unwrap();
@@ -1874,6 +1894,7 @@ fn test_parse_csi_sgr_mouse() {
             column: 19,
             row: 9,
             modifiers: KeyModifiers::empty(),
+            pixel_position: None,
         }))
     );
     let mut buf = [0; 16];
@@ -1882,6 +1903,7 @@ fn test_parse_csi_sgr_mouse() {
         column: 19,
         row: 9,
         modifiers: KeyModifiers::empty(),
+        pixel_position: None,
     })
     .encode(&mut buf, Encoding::Xterm)
     .unwrap();
@@ -1894,6 +1916,7 @@ fn test_parse_csi_sgr_mouse() {
             column: 19,
             row: 9,
             modifiers: KeyModifiers::empty(),
+            pixel_position: None,
         }))
     );
     let mut buf = [0; 16];
@@ -1902,6 +1925,7 @@ fn test_parse_csi_sgr_mouse() {
         column: 19,
         row: 9,
         modifiers: KeyModifiers::empty(),
+        pixel_position: None,
     })
     .encode(&mut buf, Encoding::Xterm)
     .unwrap();
@@ -2731,20 +2755,74 @@ fn test_numbers_with_modifiers() {
 
 #[test]
 fn test_parse_csi_u_with_shifted_keycode() {
+    // A-S-9 is equivalent to A-(
+    let Some(Event::Key(key_event)) = parse_event(b"\x1B[57:40;4u").unwrap() else {
+        panic!("expected key event");
+    };
     assert_eq!(
-        // A-S-9 is equivalent to A-(
-        parse_event(b"\x1B[57:40;4u").unwrap(),
-        Some(Event::Key(
-            KeyEvent::new(KeyCode::Char('(')).modifiers(KeyModifiers::ALT,)
-        )),
+        key_event,
+        KeyEvent::new(KeyCode::Char('9')).modifiers(KeyModifiers::ALT | KeyModifiers::SHIFT)
     );
+    assert_eq!(key_event.shifted_key, Some('('));
+
+    // A-S-minus is equivalent to A-_
+    let Some(Event::Key(key_event)) = parse_event(b"\x1B[45:95;4u").unwrap() else {
+        panic!("expected key event");
+    };
     assert_eq!(
-        // A-S-minus is equivalent to A-_
-        parse_event(b"\x1B[45:95;4u").unwrap(),
-        Some(Event::Key(
-            KeyEvent::new(KeyCode::Char('_')).modifiers(KeyModifiers::ALT,)
-        )),
+        key_event,
+        KeyEvent::new(KeyCode::Char('-')).modifiers(KeyModifiers::ALT | KeyModifiers::SHIFT)
     );
+    assert_eq!(key_event.shifted_key, Some('_'));
+}
+
+#[test]
+fn test_kitty_base_layout_key() {
+    // Ctrl+я on a Cyrillic layout is in the same position as Ctrl+z
+    let Some(Event::Key(key_event)) = parse_event(b"\x1B[1103::122;5u").unwrap() else {
+        panic!("expected key event");
+    };
+    assert_eq!(
+        key_event,
+        KeyEvent::new(KeyCode::Char('я')).modifiers(KeyModifiers::CTRL)
+    );
+    assert_eq!(key_event.shifted_key, None);
+    assert_eq!(key_event.base_layout_key, Some('z'));
+
+    let mut buf = [0; 32];
+    let written = Event::Key(key_event)
+        .encode(&mut buf, Encoding::Kitty(KittyFlags::all()))
+        .unwrap();
+    assert_eq!(buf[..written], *b"\x1B[1103::122;5u");
+
+    let Some(Event::Key(key_event)) = parse_event(b"\x1B[1103:1071:122;6u").unwrap() else {
+        panic!("expected key event");
+    };
+    assert_eq!(key_event.shifted_key, Some('Я'));
+    assert_eq!(key_event.base_layout_key, Some('z'));
+    let written = Event::Key(key_event)
+        .encode(&mut buf, Encoding::Kitty(KittyFlags::all()))
+        .unwrap();
+    assert_eq!(buf[..written], *b"\x1B[1103:1071:122;6u");
+
+    // Alternate keys are only sent when the flag is enabled
+    let written = Event::Key(key_event)
+        .encode(
+            &mut buf,
+            Encoding::Kitty(KittyFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES),
+        )
+        .unwrap();
+    assert_eq!(buf[..written], *b"\x1B[1103;6u");
+
+    // The shifted key is dropped if shift isn't pressed
+    let written = Event::Key(
+        KeyEvent::new(KeyCode::Char('9'))
+            .modifiers(KeyModifiers::CTRL)
+            .shifted_key('('),
+    )
+    .encode(&mut buf, Encoding::Kitty(KittyFlags::all()))
+    .unwrap();
+    assert_eq!(buf[..written], *b"\x1B[57;5u");
 }
 
 #[test]
@@ -2811,3 +2889,2195 @@ fn test_parse_csi_numbered_escape_code_with_types() {
         .unwrap();
     assert_eq!(buf[..written], *b"\x1B[6~");
 }
+
+#[test]
+fn test_parser_multiple_events() {
+    let mut parser = Parser::new();
+    let events = parser
+        .parse(b"\x1B[A\x1B[1;5Bab\x1B[200~paste\x1B[201~\x1B[<0;20;10M")
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(
+        events,
+        vec![
+            (Event::Key(KeyCode::Up.into()), 3),
+            (
+                Event::Key(KeyEvent::new(KeyCode::Down).modifiers(KeyModifiers::CTRL)),
+                6
+            ),
+            (Event::Key(KeyCode::Char('a').into()), 1),
+            (Event::Key(KeyCode::Char('b').into()), 1),
+            (Event::Paste("paste".to_string()), 17),
+            (
+                Event::Mouse(MouseEvent {
+                    kind: MouseEventKind::Down(MouseButton::Left),
+                    column: 19,
+                    row: 9,
+                    modifiers: KeyModifiers::empty(),
+                    pixel_position: None,
+                }),
+                11
+            ),
+        ]
+    );
+    assert!(parser.pending().is_empty());
+}
+
+#[test]
+fn test_parser_partial_sequences() {
+    let mut parser = Parser::new();
+    let events = parser
+        .parse(b"a\x1B[1;")
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(events, vec![(Event::Key(KeyCode::Char('a').into()), 1)]);
+    assert_eq!(parser.pending(), b"\x1B[1;");
+
+    let events = parser
+        .parse(b"2A\xC3")
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(
+        events,
+        vec![(
+            Event::Key(KeyEvent::new(KeyCode::Up).modifiers(KeyModifiers::SHIFT)),
+            6
+        )]
+    );
+
+    let events = parser
+        .parse(b"\xB1")
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(events, vec![(Event::Key(KeyCode::Char('ñ').into()), 2)]);
+
+    let events = parser
+        .parse(b"\x1B[200~long ")
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert!(events.is_empty());
+    let events = parser
+        .parse(b"paste\x1B[201~")
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(events, vec![(Event::Paste("long paste".to_string()), 22)]);
+}
+
+#[test]
+fn test_parser_escape() {
+    let mut parser = Parser::new();
+    let events = parser
+        .parse(b"\x1B\x1Ba\x1B\x1B[Z\x1B")
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(
+        events,
+        vec![
+            (
+                Event::Key(KeyEvent::new(KeyCode::Esc).modifiers(KeyModifiers::ALT)),
+                2
+            ),
+            (Event::Key(KeyCode::Char('a').into()), 1),
+            (
+                Event::Key(
+                    KeyEvent::new(KeyCode::Tab).modifiers(KeyModifiers::ALT | KeyModifiers::SHIFT)
+                ),
+                4
+            ),
+            (Event::Key(KeyCode::Esc.into()), 1),
+        ]
+    );
+}
+
+#[test]
+fn test_parser_invalid_sequences() {
+    let mut parser = Parser::new();
+    let mut events = parser.parse(b"\x1B[?5na\x1B[?1$yb");
+    assert!(events.next().unwrap().is_err());
+    assert_eq!(
+        events.next().unwrap().unwrap(),
+        (Event::Key(KeyCode::Char('a').into()), 1)
+    );
+    assert!(events.next().unwrap().is_err());
+    assert_eq!(
+        events.next().unwrap().unwrap(),
+        (Event::Key(KeyCode::Char('b').into()), 1)
+    );
+    assert!(events.next().is_none());
+}
+
+#[test]
+fn test_parser_escape_timeout() {
+    let mut parser = Parser::new().escape_timeout(Duration::from_secs(60));
+    assert!(parser.parse(b"\x1B").next().is_none());
+    assert!(parser.deadline().is_some());
+    let events = parser.parse(b"a").collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(
+        events,
+        vec![(
+            Event::Key(KeyEvent::new(KeyCode::Char('a')).modifiers(KeyModifiers::ALT)),
+            2
+        )]
+    );
+    assert!(parser.deadline().is_none());
+
+    assert!(parser.parse(b"\x1B").next().is_none());
+    let events = parser.flush().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(events, vec![(Event::Key(KeyCode::Esc.into()), 1)]);
+
+    assert!(parser.parse(b"\x1B[").next().is_none());
+    let events = parser.flush().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(
+        events,
+        vec![(
+            Event::Key(KeyEvent::new(KeyCode::Char('[')).modifiers(KeyModifiers::ALT)),
+            2
+        )]
+    );
+
+    assert!(parser.parse(b"\x1B[1;").next().is_none());
+    let events = parser.flush().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(
+        events,
+        vec![
+            (
+                Event::Key(KeyEvent::new(KeyCode::Char('[')).modifiers(KeyModifiers::ALT)),
+                2
+            ),
+            (Event::Key(KeyCode::Char('1').into()), 1),
+            (Event::Key(KeyCode::Char(';').into()), 1),
+        ]
+    );
+
+    assert!(parser.parse(b"\x1B[200~paste").next().is_none());
+    assert!(
+        parser.deadline().is_none(),
+        "Bracketed pastes don't time out"
+    );
+    parser.clear();
+    assert!(parser.flush().next().is_none());
+
+    // Input that's cut off isn't reported as invalid
+    assert!(parser.parse(b"\xE2\x82").next().is_none());
+    let events: Vec<_> = parser.flush().collect();
+    assert_eq!(
+        events,
+        vec![Err(ParseError::IncompleteSequence { range: 0..2 })]
+    );
+    assert!(parser.parse(b"\x1B\xE2\x82").next().is_none());
+    let events: Vec<_> = parser.flush().collect();
+    assert_eq!(
+        events,
+        vec![Err(ParseError::IncompleteSequence { range: 0..3 })]
+    );
+    let events: Vec<_> = parser.parse(b"\xE2\x28").collect();
+    assert_eq!(events, vec![Err(ParseError::InvalidUtf8 { range: 0..2 })]);
+    assert!(parser.flush().next().is_none());
+}
+
+#[test]
+fn test_parser_escape_timeout_elapsed() {
+    let mut parser = Parser::new().escape_timeout(Duration::ZERO);
+    assert!(parser.parse(b"\x1B").next().is_none());
+    let events = parser.parse(b"a").collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(
+        events,
+        vec![
+            (Event::Key(KeyCode::Esc.into()), 1),
+            (Event::Key(KeyCode::Char('a').into()), 1)
+        ]
+    );
+
+    assert!(parser.parse(b"\x1B\x1B").next().is_none());
+    let events = parser.parse(b"[Z").collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(
+        events,
+        vec![
+            (
+                Event::Key(KeyEvent::new(KeyCode::Esc).modifiers(KeyModifiers::ALT)),
+                2
+            ),
+            (Event::Key(KeyCode::Char('[').into()), 1),
+            (
+                Event::Key(KeyEvent::new(KeyCode::Char('Z')).modifiers(KeyModifiers::SHIFT)),
+                1
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(
+        parse_event(&[0xC3, 0x28]),
+        Err(ParseError::InvalidUtf8 { range: 0..2 })
+    );
+    assert_eq!(
+        parse_event(&[0x1B, 0xC3, 0x28]),
+        Err(ParseError::InvalidUtf8 { range: 1..3 })
+    );
+    assert_eq!(
+        parse_event(b"\x1B[1;2X"),
+        Err(ParseError::UnknownCsiFinal {
+            byte: b'X',
+            range: 0..6
+        })
+    );
+    assert_eq!(
+        parse_event(b"\x1B[x"),
+        Err(ParseError::UnknownCsiFinal {
+            byte: b'x',
+            range: 0..3
+        })
+    );
+    assert_eq!(
+        parse_event(b"\x1B[99999999999u"),
+        Err(ParseError::MalformedParameter { range: 2..13 })
+    );
+    assert_eq!(
+        parse_event(b"\x1B[<0;0;10M"),
+        Err(ParseError::MalformedParameter { range: 5..6 })
+    );
+    assert_eq!(
+        parse_event(b"\x1B[<98;20;10M"),
+        Err(ParseError::UnsupportedMouseButton {
+            button: 6,
+            range: 0..12
+        })
+    );
+    assert_eq!(
+        parse_event(b"\x1B[>4;2m"),
+        Err(ParseError::TerminalResponse { range: 0..7 })
+    );
+    assert_eq!(
+        parse_event(b"\x1B[?62;x2c"),
+        Err(ParseError::MalformedParameter { range: 6..8 })
+    );
+    assert_eq!(
+        parse_event(b"\x1B[99~"),
+        Err(ParseError::UnknownSequence { range: 0..5 })
+    );
+    assert_eq!(
+        parse_event(b"\x1BOz"),
+        Err(ParseError::UnknownSequence { range: 0..3 })
+    );
+
+    let err: std::io::Error = ParseError::UnknownSequence { range: 0..5 }.into();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_parser_errors() {
+    let mut parser = Parser::new();
+    let mut events = parser.parse(b"\x1B[?5na");
+    assert_eq!(
+        events.next(),
+        Some(Err(ParseError::UnknownSequence { range: 0..5 }))
+    );
+    assert_eq!(
+        events.next(),
+        Some(Ok((Event::Key(KeyCode::Char('a').into()), 1)))
+    );
+}
+
+#[test]
+fn test_parser_unknown_sequence_bounds() {
+    let mut parser = Parser::new();
+    let mut events = parser.parse(b"\x1B[>4;2ma");
+    assert_eq!(
+        events.next(),
+        Some(Err(ParseError::TerminalResponse { range: 0..7 }))
+    );
+    assert_eq!(
+        events.next(),
+        Some(Ok((Event::Key(KeyCode::Char('a').into()), 1)))
+    );
+    assert_eq!(events.next(), None);
+}
+
+#[test]
+fn test_parser_report_unknown() {
+    let mut parser = Parser::new().report_unknown_sequences(true);
+    let events: Vec<_> = parser
+        .parse(b"\x1B[?1;2$y\x1B[>4;2mx\x1BOa")
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        events,
+        vec![
+            (
+                Event::Unknown {
+                    raw: b"\x1B[?1;2$y".to_vec()
+                },
+                8
+            ),
+            (
+                Event::Unknown {
+                    raw: b"\x1B[>4;2m".to_vec()
+                },
+                7
+            ),
+            (Event::Key(KeyCode::Char('x').into()), 1),
+            (
+                Event::Unknown {
+                    raw: b"\x1BOa".to_vec()
+                },
+                3
+            ),
+        ]
+    );
+
+    let mut buf = [0; 16];
+    let written = Event::Unknown {
+        raw: b"\x1B[>4;2m".to_vec(),
+    }
+    .encode(&mut buf, Encoding::Xterm)
+    .unwrap();
+    assert_eq!(buf[..written], *b"\x1B[>4;2m");
+}
+
+#[test]
+fn test_parser_report_unknown_partial() {
+    let mut parser = Parser::new().report_unknown_sequences(true);
+    assert_eq!(parser.parse(b"\x1B[>4;").next(), None);
+    let events: Vec<_> = parser.parse(b"2mb").collect::<Result<_, _>>().unwrap();
+    assert_eq!(
+        events,
+        vec![
+            (
+                Event::Unknown {
+                    raw: b"\x1B[>4;2m".to_vec()
+                },
+                7
+            ),
+            (Event::Key(KeyCode::Char('b').into()), 1),
+        ]
+    );
+}
+
+#[test]
+fn test_parser_report_unknown_strings() {
+    let mut parser = Parser::new().report_unknown_sequences(true);
+    assert_eq!(parser.parse(b"\x1B]11;rgb:0000/0000/").next(), None);
+    let events: Vec<_> = parser
+        .parse(b"0000\x07\x1BP+r\x1B\\\x1B_Gi=1;OK\x1B\\")
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        events,
+        vec![
+            (
+                Event::Unknown {
+                    raw: b"\x1B]11;rgb:0000/0000/0000\x07".to_vec()
+                },
+                24
+            ),
+            (
+                Event::Unknown {
+                    raw: b"\x1BP+r\x1B\\".to_vec()
+                },
+                6
+            ),
+            (
+                Event::Unknown {
+                    raw: b"\x1B_Gi=1;OK\x1B\\".to_vec()
+                },
+                11
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_parser_report_unknown_unterminated_string() {
+    let mut parser = Parser::new()
+        .report_unknown_sequences(true)
+        .escape_timeout(Duration::from_millis(10));
+    assert_eq!(parser.parse(b"\x1B]").next(), None);
+    let events: Vec<_> = parser.flush().collect::<Result<_, _>>().unwrap();
+    assert_eq!(
+        events,
+        vec![(
+            Event::Key(KeyEvent::new(KeyCode::Char(']')).modifiers(KeyModifiers::ALT)),
+            2
+        )]
+    );
+}
+
+#[test]
+fn test_terminal_responses() {
+    let cases: [(&[u8], TerminalResponse); 6] = [
+        (
+            b"\x1B[?62;22;52c",
+            TerminalResponse::PrimaryDeviceAttributes {
+                conformance_level: 62,
+                features: vec![22, 52],
+            },
+        ),
+        (
+            b"\x1B[?6c",
+            TerminalResponse::PrimaryDeviceAttributes {
+                conformance_level: 6,
+                features: vec![],
+            },
+        ),
+        (
+            b"\x1B[>1;4000;29c",
+            TerminalResponse::SecondaryDeviceAttributes {
+                terminal_type: 1,
+                firmware_version: 4000,
+                rom_cartridge: 29,
+            },
+        ),
+        (
+            b"\x1B[20;10R",
+            TerminalResponse::CursorPosition { row: 19, column: 9 },
+        ),
+        (
+            b"\x1B[?15u",
+            TerminalResponse::KittyKeyboardFlags {
+                flags: KittyFlags::DISAMBIGUATE_ESCAPE_CODES
+                    | KittyFlags::REPORT_EVENT_TYPES
+                    | KittyFlags::REPORT_ALTERNATE_KEYS
+                    | KittyFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES,
+            },
+        ),
+        (
+            b"\x1BP>|kitty(0.39.1)\x1B\\",
+            TerminalResponse::TerminalVersion {
+                version: "kitty(0.39.1)".to_string(),
+            },
+        ),
+    ];
+    for (sequence, response) in cases {
+        assert_eq!(
+            parse_event(sequence).unwrap(),
+            Some(Event::Response(response.clone()))
+        );
+        let mut buf = [0; 32];
+        let written = Event::Response(response)
+            .encode(&mut buf, Encoding::Xterm)
+            .unwrap();
+        assert_eq!(buf[..written], *sequence);
+    }
+
+    assert_eq!(
+        parse_event(b"\x1B[>1;277c").unwrap(),
+        Some(Event::Response(
+            TerminalResponse::SecondaryDeviceAttributes {
+                terminal_type: 1,
+                firmware_version: 277,
+                rom_cartridge: 0,
+            }
+        ))
+    );
+    assert_eq!(parse_event(b"\x1BP>|kitty").unwrap(), None);
+}
+
+#[test]
+fn test_parser_cursor_position() {
+    let mut parser = Parser::new();
+    let events: Vec<_> = parser
+        .parse(b"\x1B[1;2R\x1B[5;2R")
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        events,
+        vec![
+            (
+                Event::Key(KeyEvent::new(KeyCode::F(3)).modifiers(KeyModifiers::SHIFT)),
+                6
+            ),
+            (
+                Event::Response(TerminalResponse::CursorPosition { row: 4, column: 1 }),
+                6
+            ),
+        ]
+    );
+
+    parser.expect_cursor_position();
+    let events: Vec<_> = parser
+        .parse(b"\x1B[1;2R\x1B[1;2R")
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        events,
+        vec![
+            (
+                Event::Response(TerminalResponse::CursorPosition { row: 0, column: 1 }),
+                6
+            ),
+            (
+                Event::Key(KeyEvent::new(KeyCode::F(3)).modifiers(KeyModifiers::SHIFT)),
+                6
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_parser_terminal_version() {
+    let mut parser = Parser::new();
+    assert_eq!(parser.parse(b"\x1BP>|WezTerm ").next(), None);
+    let events: Vec<_> = parser
+        .parse(b"20240203\x1B\\a")
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        events,
+        vec![
+            (
+                Event::Response(TerminalResponse::TerminalVersion {
+                    version: "WezTerm 20240203".to_string()
+                }),
+                22
+            ),
+            (Event::Key(KeyCode::Char('a').into()), 1),
+        ]
+    );
+}
+
+#[test]
+fn test_kitty_associated_text() {
+    let Some(Event::Key(key_event)) = parse_event(b"\x1B[97;;233u").unwrap() else {
+        panic!("expected key event");
+    };
+    assert_eq!(key_event, KeyEvent::new(KeyCode::Char('a')));
+    assert_eq!(key_event.text.as_deref(), Some("é"));
+
+    let Some(Event::Key(key_event)) = parse_event(b"\x1B[97;2;65u").unwrap() else {
+        panic!("expected key event");
+    };
+    assert_eq!(
+        key_event,
+        KeyEvent::new(KeyCode::Char('a')).modifiers(KeyModifiers::SHIFT)
+    );
+    assert_eq!(key_event.text.as_deref(), Some("A"));
+
+    let Some(Event::Key(key_event)) = parse_event(b"\x1B[101;1;101:769u").unwrap() else {
+        panic!("expected key event");
+    };
+    assert_eq!(key_event.text.as_deref(), Some("e\u{301}"));
+
+    assert_eq!(
+        parse_event(b"\x1B[97;1;1114112u"),
+        Err(ParseError::MalformedParameter { range: 7..14 })
+    );
+
+    let Some(Event::Key(key_event)) =
+        parse_event(b"\x1B[97;1;128512:128512:128512:128512:128512:128512:128512u").unwrap()
+    else {
+        panic!("expected key event");
+    };
+    assert_eq!(key_event.text.as_deref(), Some("😀".repeat(7).as_str()));
+    assert_eq!(
+        parse_event(b"\x1B[97;1;128512:128512:128512:128512:128512:128512:128512:128512u"),
+        Err(ParseError::MalformedParameter { range: 7..62 })
+    );
+
+    let flags = KittyFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES | KittyFlags::REPORT_ASSOCIATED_TEXT;
+    let event = Event::Key(KeyEvent::new(KeyCode::Char('a')).text(KeyText::new("é").unwrap()));
+    let mut buf = [0; 16];
+    let written = event.encode(&mut buf, Encoding::Kitty(flags)).unwrap();
+    assert_eq!(buf[..written], *b"\x1B[97;1;233u");
+    let Some(Event::Key(key_event)) = parse_event(&buf[..written]).unwrap() else {
+        panic!("expected key event");
+    };
+    assert_eq!(key_event.text.as_deref(), Some("é"));
+
+    let written = event
+        .encode(
+            &mut buf,
+            Encoding::Kitty(KittyFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES),
+        )
+        .unwrap();
+    assert_eq!(buf[..written], *b"\x1B[97u");
+
+    let written = Event::Key(
+        KeyEvent::new(KeyCode::Char('a'))
+            .kind(KeyEventKind::Release)
+            .text(KeyText::new("a").unwrap()),
+    )
+    .encode(&mut buf, Encoding::Kitty(KittyFlags::all()))
+    .unwrap();
+    assert_eq!(buf[..written], *b"\x1B[97;1:3u");
+
+    let written = event
+        .encode(
+            &mut buf,
+            Encoding::Kitty(KittyFlags::DISAMBIGUATE_ESCAPE_CODES),
+        )
+        .unwrap();
+    assert_eq!(buf[..written], *"é".as_bytes());
+}
+
+#[test]
+fn test_key_text() {
+    let text = KeyText::new("abc").unwrap();
+    assert_eq!(text.as_str(), "abc");
+    assert_eq!(format!("{text:?}"), "\"abc\"");
+    assert!(KeyText::new(&"a".repeat(KeyText::CAPACITY)).is_some());
+    assert!(KeyText::new(&"a".repeat(KeyText::CAPACITY + 1)).is_none());
+}
+
+#[test]
+fn test_key_event_ord_matches_eq() {
+    let key_event = KeyEvent::new(KeyCode::Char('a'));
+    let with_details = key_event
+        .text(KeyText::new("z").unwrap())
+        .shifted_key('A')
+        .base_layout_key('q');
+    assert_eq!(key_event, with_details);
+    assert_eq!(key_event.cmp(&with_details), std::cmp::Ordering::Equal);
+
+    let upper = KeyEvent::new(KeyCode::Char('A'));
+    let shift = KeyEvent::new(KeyCode::Char('a')).modifiers(KeyModifiers::SHIFT);
+    assert_eq!(upper, shift);
+    assert_eq!(upper.cmp(&shift), std::cmp::Ordering::Equal);
+
+    assert!(KeyEvent::new(KeyCode::Char('a')) < KeyEvent::new(KeyCode::Char('b')));
+}
+
+#[test]
+fn test_kitty_flags_protocol() {
+    assert_eq!(u8::from(KittyFlags::DISAMBIGUATE_ESCAPE_CODES), 1);
+    assert_eq!(u8::from(KittyFlags::REPORT_EVENT_TYPES), 2);
+    assert_eq!(u8::from(KittyFlags::REPORT_ALTERNATE_KEYS), 4);
+    assert_eq!(u8::from(KittyFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES), 8);
+    assert_eq!(u8::from(KittyFlags::REPORT_ASSOCIATED_TEXT), 16);
+    assert_eq!(KittyFlags::from(31), KittyFlags::all());
+    assert_eq!(
+        KittyFlags::from(0b100101),
+        KittyFlags::DISAMBIGUATE_ESCAPE_CODES | KittyFlags::REPORT_ALTERNATE_KEYS
+    );
+
+    let commands: [(KittyFlagsCommand, &[u8]); 6] = [
+        (KittyFlagsCommand::Push(KittyFlags::all()), b"\x1B[>31u"),
+        (KittyFlagsCommand::Pop(1), b"\x1B[<u"),
+        (KittyFlagsCommand::Pop(3), b"\x1B[<3u"),
+        (
+            KittyFlagsCommand::Set(
+                KittyFlags::DISAMBIGUATE_ESCAPE_CODES,
+                KittyFlagsMode::Replace,
+            ),
+            b"\x1B[=1;1u",
+        ),
+        (
+            KittyFlagsCommand::Set(KittyFlags::REPORT_EVENT_TYPES, KittyFlagsMode::Disable),
+            b"\x1B[=2;3u",
+        ),
+        (KittyFlagsCommand::Query, b"\x1B[?u"),
+    ];
+    for (command, sequence) in commands {
+        let mut buf = [0; 16];
+        let written = command.encode(&mut buf).unwrap();
+        assert_eq!(buf[..written], *sequence);
+        assert_eq!(command.to_string().as_bytes(), sequence);
+    }
+
+    assert_eq!(
+        parse_event(b"\x1B[?5u").unwrap(),
+        Some(Event::Response(TerminalResponse::KittyKeyboardFlags {
+            flags: KittyFlags::DISAMBIGUATE_ESCAPE_CODES | KittyFlags::REPORT_ALTERNATE_KEYS
+        }))
+    );
+}
+
+#[test]
+fn test_encode_to_writer() {
+    let paste = "a".repeat(10_000);
+    let event = Event::Paste(paste.clone());
+    let mut buf = Vec::new();
+    let written = event.encode_to_writer(&mut buf, Encoding::Xterm).unwrap();
+    assert_eq!(written, paste.len() + 12);
+    assert_eq!(buf.len(), written);
+    assert_eq!(parse_event(&buf).unwrap(), Some(event.clone()));
+    assert_eq!(event.encode_to_vec(Encoding::Xterm).unwrap(), buf);
+
+    let mut small_buf = [0; 16];
+    assert_eq!(
+        event
+            .encode(&mut small_buf, Encoding::Xterm)
+            .unwrap_err()
+            .kind(),
+        std::io::ErrorKind::WriteZero
+    );
+
+    let event = Event::Key(KeyEvent::new(KeyCode::Up).modifiers(KeyModifiers::CTRL));
+    let mut buf = Vec::new();
+    let written = event.encode_to_writer(&mut buf, Encoding::Xterm).unwrap();
+    assert_eq!(buf[..written], *b"\x1B[1;5A");
+    assert_eq!(event.encode_to_vec(Encoding::Xterm).unwrap(), b"\x1B[1;5A");
+}
+
+#[test]
+fn test_max_encoded_len() {
+    let mut buf = [0; 256];
+    let flags = KittyFlags::all();
+    let events = [
+        Event::Key(
+            KeyEvent::new(KeyCode::Char('\u{10FFFF}'))
+                .modifiers(KeyModifiers::all())
+                .kind(KeyEventKind::Repeat)
+                .state(KeyEventState::CAPS_LOCK | KeyEventState::NUM_LOCK)
+                .shifted_key('\u{10FFFE}')
+                .base_layout_key('\u{10FFFD}')
+                .text(KeyText::new(&"a".repeat(KeyText::CAPACITY)).unwrap()),
+        ),
+        Event::Key(
+            KeyEvent::new(KeyCode::F(12))
+                .modifiers(KeyModifiers::SHIFT | KeyModifiers::ALT | KeyModifiers::CTRL),
+        ),
+        Event::Key(KeyEvent::new(KeyCode::Char('é')).modifiers(KeyModifiers::ALT)),
+        Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Drag(MouseButton::Right),
+            column: u16::MAX - 1,
+            row: u16::MAX - 1,
+            modifiers: KeyModifiers::SHIFT | KeyModifiers::ALT | KeyModifiers::CTRL,
+            pixel_position: None,
+        }),
+        Event::Response(TerminalResponse::CursorPosition {
+            row: u16::MAX,
+            column: u16::MAX,
+        }),
+        Event::Response(TerminalResponse::PrimaryDeviceAttributes {
+            conformance_level: u16::MAX,
+            features: vec![u16::MAX; 4],
+        }),
+        Event::Response(TerminalResponse::SecondaryDeviceAttributes {
+            terminal_type: u16::MAX,
+            firmware_version: u32::MAX,
+            rom_cartridge: u16::MAX,
+        }),
+    ];
+    for event in events {
+        for encoding in [Encoding::Xterm, Encoding::Kitty(flags)] {
+            let Ok(written) = event.encode(&mut buf, encoding) else {
+                continue;
+            };
+            assert!(
+                written <= event.max_encoded_len(encoding),
+                "{event:?} {encoding:?}"
+            );
+            assert_eq!(event.encode_to_vec(encoding).unwrap(), buf[..written]);
+        }
+    }
+}
+
+#[test]
+fn test_mode_encoder_keys() {
+    let mut encoder = ModeEncoder::new();
+    let up = Event::Key(KeyEvent::new(KeyCode::Up));
+    let ctrl_up = Event::Key(KeyEvent::new(KeyCode::Up).modifiers(KeyModifiers::CTRL));
+    let keypad_one = Event::Key(KeyEvent::new(KeyCode::Char('1')).state(KeyEventState::KEYPAD));
+    assert_eq!(encoder.encode_to_vec(&up).unwrap(), b"\x1B[A");
+    assert_eq!(encoder.encode_to_vec(&keypad_one).unwrap(), b"1");
+
+    // Sequences can be split across writes
+    encoder.process_output(b"hello\x1B[?1;");
+    encoder.process_output(b"2004h\x1B=");
+    assert!(encoder.application_cursor_keys());
+    assert!(encoder.application_keypad());
+    assert!(encoder.bracketed_paste());
+    assert_eq!(encoder.encode_to_vec(&up).unwrap(), b"\x1BOA");
+    assert_eq!(encoder.encode_to_vec(&ctrl_up).unwrap(), b"\x1B[1;5A");
+    assert_eq!(encoder.encode_to_vec(&keypad_one).unwrap(), b"\x1BOq");
+
+    // String contents are skipped
+    encoder.process_output(b"\x1B]0;=[?1l\x1B\\\x1BP=\x07\x1B>");
+    assert!(encoder.application_cursor_keys());
+    assert!(!encoder.application_keypad());
+
+    encoder.process_output(b"\x1B[>1u");
+    assert_eq!(
+        encoder.encoding(),
+        Encoding::Kitty(KittyFlags::DISAMBIGUATE_ESCAPE_CODES)
+    );
+    assert_eq!(
+        encoder
+            .encode_to_vec(&Event::Key(KeyEvent::new(KeyCode::Esc)))
+            .unwrap(),
+        b"\x1B[27u"
+    );
+    encoder.process_output(b"\x1B[=8;2u");
+    assert_eq!(
+        encoder.kitty_flags(),
+        KittyFlags::DISAMBIGUATE_ESCAPE_CODES | KittyFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES
+    );
+
+    // The alternate screen has its own stack
+    encoder.process_output(b"\x1B[?1049h");
+    assert_eq!(
+        encoder.encoding(),
+        Encoding::XtermWith(encoder.xterm_options())
+    );
+    encoder.process_output(b"\x1B[?1049l\x1B[<u");
+    assert_eq!(
+        encoder.encoding(),
+        Encoding::XtermWith(encoder.xterm_options())
+    );
+
+    encoder.process_output(b"\x1Bc");
+    assert_eq!(encoder, ModeEncoder::new());
+}
+
+#[test]
+fn test_mode_encoder_events() {
+    let mut encoder = ModeEncoder::new();
+    let mouse_event = |kind| {
+        Event::Mouse(MouseEvent {
+            kind,
+            column: 1,
+            row: 2,
+            modifiers: KeyModifiers::NONE,
+            pixel_position: None,
+        })
+    };
+    let down = mouse_event(MouseEventKind::Down(MouseButton::Left));
+    let up = mouse_event(MouseEventKind::Up(MouseButton::Left));
+    let drag = mouse_event(MouseEventKind::Drag(MouseButton::Left));
+    let moved = mouse_event(MouseEventKind::Moved);
+    let paste = Event::Paste("a\nb".to_string());
+
+    for event in [&down, &drag, &moved, &Event::FocusGained] {
+        assert_eq!(encoder.encode_to_vec(event).unwrap(), b"");
+    }
+    assert_eq!(encoder.encode_to_vec(&paste).unwrap(), b"a\rb");
+
+    encoder.process_output(b"\x1B[?1000h\x1B[?1004h\x1B[?2004h");
+    assert_eq!(encoder.mouse_tracking(), Some(MouseTracking::Normal));
+    assert_eq!(encoder.encode_to_vec(&down).unwrap(), b"\x1B[M\x20\x22\x23");
+    assert_eq!(encoder.encode_to_vec(&up).unwrap(), b"\x1B[M\x23\x22\x23");
+    assert_eq!(encoder.encode_to_vec(&drag).unwrap(), b"");
+    assert_eq!(encoder.encode_to_vec(&Event::FocusLost).unwrap(), b"\x1B[O");
+    assert_eq!(
+        encoder.encode_to_vec(&paste).unwrap(),
+        b"\x1B[200~a\nb\x1B[201~"
+    );
+
+    encoder.process_output(b"\x1B[?1002;1006h");
+    assert_eq!(encoder.encode_to_vec(&up).unwrap(), b"\x1B[<0;2;3m");
+    assert_eq!(encoder.encode_to_vec(&drag).unwrap(), b"\x1B[<32;2;3M");
+    assert_eq!(encoder.encode_to_vec(&moved).unwrap(), b"");
+
+    encoder.process_output(b"\x1B[?1003h");
+    assert_eq!(encoder.encode_to_vec(&moved).unwrap(), b"\x1B[<35;2;3M");
+
+    encoder.process_output(b"\x1B[?1000l");
+    assert_eq!(encoder.mouse_tracking(), None);
+    assert_eq!(encoder.encode_to_vec(&down).unwrap(), b"");
+}
+
+#[test]
+fn test_xterm_options() {
+    let options = XtermOptions::new()
+        .application_cursor_keys(true)
+        .application_keypad(true);
+    let keypad = |code| Event::Key(KeyEvent::new(code).state(KeyEventState::KEYPAD));
+    let cases = [
+        (Event::Key(KeyEvent::new(KeyCode::Up)), "\x1BOA"),
+        (Event::Key(KeyEvent::new(KeyCode::Down)), "\x1BOB"),
+        (Event::Key(KeyEvent::new(KeyCode::Right)), "\x1BOC"),
+        (Event::Key(KeyEvent::new(KeyCode::Left)), "\x1BOD"),
+        (Event::Key(KeyEvent::new(KeyCode::Home)), "\x1BOH"),
+        (Event::Key(KeyEvent::new(KeyCode::End)), "\x1BOF"),
+        (keypad(KeyCode::Char('0')), "\x1BOp"),
+        (keypad(KeyCode::Char('9')), "\x1BOy"),
+        (keypad(KeyCode::Char('*')), "\x1BOj"),
+        (keypad(KeyCode::Char('/')), "\x1BOo"),
+        (keypad(KeyCode::Char('=')), "\x1BOX"),
+        (keypad(KeyCode::Enter), "\x1BOM"),
+    ];
+    for (event, encoded) in cases {
+        assert_eq!(
+            event.encode_to_vec(Encoding::XtermWith(options)).unwrap(),
+            encoded.as_bytes(),
+        );
+        assert_eq!(parse_event(encoded.as_bytes()).unwrap(), Some(event));
+    }
+
+    // Modified keys and keys outside of the keypad are unaffected
+    let event = Event::Key(KeyEvent::new(KeyCode::Up).modifiers(KeyModifiers::SHIFT));
+    assert_eq!(
+        event.encode_to_vec(Encoding::XtermWith(options)).unwrap(),
+        b"\x1B[1;2A"
+    );
+    let event = Event::Key(KeyEvent::new(KeyCode::Char('1')));
+    assert_eq!(
+        event.encode_to_vec(Encoding::XtermWith(options)).unwrap(),
+        b"1"
+    );
+    let event = keypad(KeyCode::Char('1'));
+    assert_eq!(
+        event
+            .encode_to_vec(Encoding::XtermWith(XtermOptions::new()))
+            .unwrap(),
+        event.encode_to_vec(Encoding::Xterm).unwrap(),
+    );
+
+    // Keys that fall back to the legacy encoding in Kitty mode also respect the options
+    let mut encoder = ModeEncoder::new();
+    encoder.process_output(b"\x1B[?1h\x1B[>1u");
+    assert_eq!(
+        encoder
+            .encode_to_vec(&Event::Key(KeyEvent::new(KeyCode::Up)))
+            .unwrap(),
+        b"\x1BOA"
+    );
+}
+
+#[test]
+fn test_modify_other_keys() {
+    let key = |c, modifiers| Event::Key(KeyEvent::new(KeyCode::Char(c)).modifiers(modifiers));
+    let ctrl_shift = KeyModifiers::CTRL | KeyModifiers::SHIFT;
+    let cases = [
+        (key('a', ctrl_shift), 1, "\x1B[27;6;65~"),
+        (key('1', KeyModifiers::CTRL), 1, "\x1B[27;5;49~"),
+        (
+            key(';', KeyModifiers::CTRL | KeyModifiers::ALT),
+            1,
+            "\x1B[27;7;59~",
+        ),
+        (key('a', KeyModifiers::SUPER), 1, "\x1B[27;9;97~"),
+        (key('a', KeyModifiers::CTRL), 2, "\x1B[27;5;97~"),
+        (key('a', KeyModifiers::ALT), 2, "\x1B[27;3;97~"),
+        (
+            Event::Key(KeyEvent::new(KeyCode::Enter).modifiers(KeyModifiers::CTRL)),
+            2,
+            "\x1B[27;5;13~",
+        ),
+        (
+            Event::Key(KeyEvent::new(KeyCode::Backspace).modifiers(KeyModifiers::SHIFT)),
+            2,
+            "\x1B[27;2;127~",
+        ),
+    ];
+    for (event, level, encoded) in cases {
+        assert_eq!(
+            event
+                .encode_to_vec(Encoding::ModifyOtherKeys(level))
+                .unwrap(),
+            encoded.as_bytes(),
+        );
+        assert_eq!(parse_event(encoded.as_bytes()).unwrap(), Some(event));
+    }
+
+    // Keys with a well-known legacy encoding
+    let cases = [
+        (key('a', KeyModifiers::CTRL), 1),
+        (key('a', KeyModifiers::ALT), 1),
+        (key('A', KeyModifiers::SHIFT), 2),
+        (key('!', KeyModifiers::NONE), 2),
+        (
+            Event::Key(KeyEvent::new(KeyCode::Tab).modifiers(KeyModifiers::SHIFT)),
+            2,
+        ),
+        (
+            Event::Key(KeyEvent::new(KeyCode::Up).modifiers(KeyModifiers::CTRL)),
+            2,
+        ),
+        (key('1', KeyModifiers::CTRL), 0),
+    ];
+    for (event, level) in cases {
+        assert_eq!(
+            event.encode_to_vec(Encoding::ModifyOtherKeys(level)).ok(),
+            event.encode_to_vec(Encoding::Xterm).ok(),
+            "{event:?}"
+        );
+    }
+
+    assert_eq!(
+        parse_event(b"\x1B[27;5;9~").unwrap(),
+        Some(Event::Key(
+            KeyEvent::new(KeyCode::Tab).modifiers(KeyModifiers::CTRL)
+        ))
+    );
+    assert_eq!(
+        parse_event(b"\x1B[27;5~"),
+        Err(ParseError::MalformedParameter { range: 0..7 })
+    );
+
+    let mut encoder = ModeEncoder::new();
+    encoder.process_output(b"\x1B[>4;2m");
+    assert_eq!(encoder.encoding(), Encoding::ModifyOtherKeys(2));
+    encoder.process_output(b"\x1B[>4m");
+    assert_eq!(encoder.modify_other_keys(), 0);
+}
+
+#[test]
+fn test_mouse_encodings() {
+    let mouse_event = |kind, column, row| {
+        Event::Mouse(MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::CTRL,
+            pixel_position: None,
+        })
+    };
+    let down = mouse_event(MouseEventKind::Down(MouseButton::Right), 1, 2);
+    let up = mouse_event(MouseEventKind::Up(MouseButton::Left), 1, 2);
+    let far = mouse_event(MouseEventKind::Drag(MouseButton::Left), 300, 2);
+    let encoding =
+        |mouse_encoding| Encoding::XtermWith(XtermOptions::new().mouse_encoding(mouse_encoding));
+    let cases: [(_, _, &[u8]); 11] = [
+        (MouseEncoding::X10, &down, b"\x1B[M\x32\x22\x23"),
+        (MouseEncoding::X10, &up, b"\x1B[M\x33\x22\x23"),
+        (MouseEncoding::Utf8, &down, b"\x1B[M\x32\x22\x23"),
+        (MouseEncoding::Utf8, &up, b"\x1B[M\x33\x22\x23"),
+        (
+            MouseEncoding::Utf8,
+            &far,
+            "\x1B[M\x50\u{14D}\x23".as_bytes(),
+        ),
+        (MouseEncoding::Urxvt, &down, b"\x1B[50;2;3M"),
+        (MouseEncoding::Urxvt, &up, b"\x1B[51;2;3M"),
+        (MouseEncoding::Urxvt, &far, b"\x1B[80;301;3M"),
+        (MouseEncoding::Sgr, &down, b"\x1B[<18;2;3M"),
+        (MouseEncoding::Sgr, &up, b"\x1B[<16;2;3m"),
+        (MouseEncoding::Sgr, &far, b"\x1B[<48;301;3M"),
+    ];
+    for (mouse_encoding, event, encoded) in cases {
+        assert_eq!(
+            event.encode_to_vec(encoding(mouse_encoding)).unwrap(),
+            encoded,
+            "{mouse_encoding:?} {event:?}"
+        );
+        let mut parser = Parser::new().mouse_encoding(mouse_encoding);
+        let events: Vec<_> = parser.parse(encoded).collect::<Result<_, _>>().unwrap();
+        assert_eq!(events, vec![(event.clone(), encoded.len())]);
+    }
+    assert_eq!(
+        Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Moved,
+            column: 0,
+            row: 0,
+            modifiers: KeyModifiers::NONE,
+            pixel_position: None,
+        })
+        .encode_to_vec(Encoding::Xterm)
+        .unwrap(),
+        b"\x1B[<35;1;1M"
+    );
+
+    // Positions that are too large for the encoding
+    assert!(far.encode_to_vec(encoding(MouseEncoding::X10)).is_err());
+    let far = mouse_event(MouseEventKind::Moved, 2015, 0);
+    assert!(far.encode_to_vec(encoding(MouseEncoding::Utf8)).is_err());
+    let max = mouse_event(MouseEventKind::Moved, u16::MAX, u16::MAX);
+    assert!(max.encode_to_vec(encoding(MouseEncoding::X10)).is_err());
+    assert!(max.encode_to_vec(encoding(MouseEncoding::Utf8)).is_err());
+    assert_eq!(
+        max.encode_to_vec(encoding(MouseEncoding::Urxvt)).unwrap(),
+        b"\x1B[83;65536;65536M"
+    );
+    assert_eq!(
+        max.encode_to_vec(encoding(MouseEncoding::Sgr)).unwrap(),
+        b"\x1B[<51;65536;65536M"
+    );
+
+    // UTF-8 sequences can be split in the middle of a character
+    let mut parser = Parser::new().mouse_encoding(MouseEncoding::Utf8);
+    assert_eq!(parser.parse(b"\x1B[M\x50\xC5").count(), 0);
+    let events: Vec<_> = parser.parse(b"\x8D\x23").collect::<Result<_, _>>().unwrap();
+    assert_eq!(
+        events,
+        vec![(
+            mouse_event(MouseEventKind::Drag(MouseButton::Left), 300, 2),
+            7
+        )]
+    );
+
+    let mut encoder = ModeEncoder::new();
+    encoder.process_output(b"\x1B[?1000h\x1B[?1015h\x1B[?1006h");
+    assert_eq!(encoder.mouse_encoding(), MouseEncoding::Sgr);
+    encoder.process_output(b"\x1B[?1015l");
+    assert_eq!(encoder.mouse_encoding(), MouseEncoding::Sgr);
+    encoder.process_output(b"\x1B[?1006l");
+    assert_eq!(encoder.mouse_encoding(), MouseEncoding::X10);
+    encoder.process_output(b"\x1B[?1015h");
+    assert_eq!(encoder.encode_to_vec(&down).unwrap(), b"\x1B[50;2;3M");
+}
+
+#[test]
+fn test_pixel_mouse_position() {
+    let event = Event::Mouse(MouseEvent {
+        kind: MouseEventKind::Down(MouseButton::Left),
+        column: 0,
+        row: 0,
+        modifiers: KeyModifiers::SHIFT,
+        pixel_position: Some(PixelPosition { x: 1000, y: 0 }),
+    });
+    let encoding =
+        Encoding::XtermWith(XtermOptions::new().mouse_encoding(MouseEncoding::SgrPixels));
+    assert_eq!(event.encode_to_vec(encoding).unwrap(), b"\x1B[<4;1001;1M");
+
+    let mut parser = Parser::new().mouse_encoding(MouseEncoding::SgrPixels);
+    let events: Vec<_> = parser
+        .parse(b"\x1B[<4;1001;1M")
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(events, vec![(event, 12)]);
+
+    // Without the pixel encoding, these are cell positions
+    assert_eq!(
+        parse_event(b"\x1B[<4;1001;1M").unwrap(),
+        Some(Event::Mouse(MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column: 1000,
+            row: 0,
+            modifiers: KeyModifiers::SHIFT,
+            pixel_position: None,
+        }))
+    );
+
+    // Encoding pixels requires a pixel position
+    let event = Event::Mouse(MouseEvent {
+        kind: MouseEventKind::Moved,
+        column: 1,
+        row: 1,
+        modifiers: KeyModifiers::NONE,
+        pixel_position: None,
+    });
+    assert!(event.encode_to_vec(encoding).is_err());
+}
+
+#[test]
+fn test_extra_mouse_buttons() {
+    let mouse_event = |kind| {
+        Event::Mouse(MouseEvent {
+            kind,
+            column: 4,
+            row: 5,
+            modifiers: KeyModifiers::NONE,
+            pixel_position: None,
+        })
+    };
+    let cases = [
+        (MouseEventKind::Down(MouseButton::Back), "\x1B[<128;5;6M"),
+        (MouseEventKind::Up(MouseButton::Forward), "\x1B[<129;5;6m"),
+        (
+            MouseEventKind::Down(MouseButton::Other(10)),
+            "\x1B[<130;5;6M",
+        ),
+        (
+            MouseEventKind::Drag(MouseButton::Other(11)),
+            "\x1B[<163;5;6M",
+        ),
+    ];
+    for (kind, encoded) in cases {
+        let event = mouse_event(kind);
+        assert_eq!(
+            event.encode_to_vec(Encoding::Xterm).unwrap(),
+            encoded.as_bytes()
+        );
+        assert_eq!(parse_event(encoded.as_bytes()).unwrap(), Some(event));
+    }
+
+    let x10 = Encoding::XtermWith(XtermOptions::new().mouse_encoding(MouseEncoding::X10));
+    let event = mouse_event(MouseEventKind::Down(MouseButton::Forward));
+    assert_eq!(event.encode_to_vec(x10).unwrap(), b"\x1B[M\xA1\x25\x26");
+    assert_eq!(parse_event(b"\x1B[M\xA1\x25\x26").unwrap(), Some(event));
+    // Releases don't include the button
+    let event = mouse_event(MouseEventKind::Up(MouseButton::Back));
+    assert_eq!(event.encode_to_vec(x10).unwrap(), b"\x1B[M\x23\x25\x26");
+
+    let event = mouse_event(MouseEventKind::Down(MouseButton::Other(4)));
+    assert!(event.encode_to_vec(Encoding::Xterm).is_err());
+}
+
+#[test]
+fn test_resize_events() {
+    let event = Event::Resize {
+        rows: 24,
+        cols: 80,
+        pixel_size: Some(PixelSize {
+            width: 800,
+            height: 480,
+        }),
+    };
+    let encoded = b"\x1B[48;24;80;480;800t";
+    assert_eq!(event.encode_to_vec(Encoding::Xterm).unwrap(), encoded);
+    assert_eq!(parse_event(encoded).unwrap(), Some(event));
+
+    let event = Event::Resize {
+        rows: 24,
+        cols: 80,
+        pixel_size: None,
+    };
+    let encoded = b"\x1B[48;24;80;0;0t";
+    assert_eq!(event.encode_to_vec(Encoding::Xterm).unwrap(), encoded);
+    assert_eq!(parse_event(encoded).unwrap(), Some(event.clone()));
+    // XTWINOPS text area size report
+    assert_eq!(parse_event(b"\x1B[8;24;80t").unwrap(), Some(event.clone()));
+    assert!(parse_event(b"\x1B[48;24t").is_err());
+
+    let mut encoder = ModeEncoder::new();
+    assert_eq!(encoder.encode_to_vec(&event).unwrap(), b"");
+    encoder.process_output(b"\x1B[?2048h");
+    assert!(encoder.in_band_resize());
+    assert_eq!(encoder.encode_to_vec(&event).unwrap(), encoded);
+}
+
+#[test]
+fn test_paste_sanitization() {
+    let event = Event::Paste("ls\x1B[201~\x1B[20\x1B[201~1~rm\r\n\u{9b}".to_string());
+    let cases = [
+        (
+            PasteSanitization::Unchanged,
+            "ls\x1B[201~\x1B[20\x1B[201~1~rm\r\n\u{9b}",
+        ),
+        (PasteSanitization::StripTerminators, "lsrm\r\n\u{9b}"),
+        (PasteSanitization::StripControls, "ls[201~[20[201~1~rm\r\n"),
+        (
+            PasteSanitization::EscapeControls,
+            "ls^[[201~^[[20^[[201~1~rm\r\nM-^[",
+        ),
+    ];
+    // The sanitization applies to every encoding
+    let modes: [&[u8]; 3] = [b"", b"\x1B[>4;2m", b"\x1B[>1u"];
+    for (sanitization, text) in cases {
+        let expected = format!("\x1B[200~{text}\x1B[201~");
+        for mode in modes {
+            let mut encoder = ModeEncoder::new().paste_sanitization(sanitization);
+            encoder.process_output(b"\x1B[?2004h");
+            encoder.process_output(mode);
+            assert_eq!(encoder.encode_to_vec(&event).unwrap(), expected.as_bytes());
+            let mut buf = Vec::new();
+            encoder.encode_to_writer(&event, &mut buf).unwrap();
+            assert_eq!(buf, expected.as_bytes());
+        }
+    }
+    assert_eq!(
+        PasteSanitization::StripTerminators.sanitize("\x1B[20\x1B[201~1~"),
+        ""
+    );
+    assert_eq!(
+        PasteSanitization::StripTerminators.sanitize("ls\u{9b}201~rm"),
+        "lsrm"
+    );
+    assert_eq!(
+        PasteSanitization::StripTerminators.sanitize("\u{9b}20\x1B[201~1~\x1B[20\u{9b}201~1~"),
+        ""
+    );
+
+    // Pastes are encoded unchanged without a mode encoder
+    let unchanged = "\x1B[200~ls\x1B[201~\x1B[20\x1B[201~1~rm\r\n\u{9b}\x1B[201~";
+    for encoding in [
+        Encoding::Xterm,
+        Encoding::ModifyOtherKeys(2),
+        Encoding::Kitty(KittyFlags::all()),
+    ] {
+        assert_eq!(event.encode_to_vec(encoding).unwrap(), unchanged.as_bytes());
+    }
+
+    let mut encoder = ModeEncoder::new().paste_sanitization(PasteSanitization::StripControls);
+    assert_eq!(
+        encoder.encode_to_vec(&event).unwrap(),
+        b"ls[201~[20[201~1~rm\r"
+    );
+    encoder.process_output(b"\x1B[?2004h\x1Bc\x1B[?2004h");
+    assert_eq!(
+        encoder.encode_to_vec(&event).unwrap(),
+        b"\x1B[200~ls[201~[20[201~1~rm\r\n\x1B[201~"
+    );
+}
+
+#[test]
+fn test_parser_paste_limits() {
+    let paste = |text: &str| Event::Paste(text.to_string());
+    let parse = |parser: &mut Parser, input: &[u8]| {
+        parser
+            .parse(input)
+            .map(|event| event.unwrap().0)
+            .collect::<Vec<_>>()
+    };
+
+    // Pastes within the limit are unaffected
+    let mut parser = Parser::new().max_paste_len(5);
+    assert_eq!(
+        parse(&mut parser, b"\x1B[200~short\x1B[201~a"),
+        vec![paste("short"), Event::Key(KeyCode::Char('a').into())]
+    );
+
+    // The rest of a long paste is discarded
+    assert_eq!(parse(&mut parser, b"\x1B[200~tru"), vec![]);
+    assert_eq!(parse(&mut parser, b"ncated paste"), vec![paste("trunc")]);
+    assert_eq!(parse(&mut parser, b" continues\x1B[2"), vec![]);
+    assert_eq!(parser.pending(), b"es\x1B[2");
+    assert_eq!(
+        parse(&mut parser, b"01~b"),
+        vec![Event::Key(KeyCode::Char('b').into())]
+    );
+    assert_eq!(
+        parse(&mut parser, b"\x1B[200~truncated\x1B[201~"),
+        vec![paste("trunc")]
+    );
+
+    // Streamed pastes are returned in chunks, without splitting characters
+    let mut parser = Parser::new().max_paste_len(4).stream_pastes(true);
+    assert_eq!(
+        parse(&mut parser, b"\x1B[200~stre\xC3\xA1med \xC3"),
+        vec![paste("stre")]
+    );
+    assert_eq!(
+        parse(&mut parser, b"\xA1\x1B[201~c"),
+        vec![
+            paste("\u{e1}me"),
+            paste("d \u{e1}"),
+            Event::Key(KeyCode::Char('c').into())
+        ]
+    );
+    assert_eq!(
+        parse(&mut parser, b"\x1B[200~abcd\x1B[201~"),
+        vec![paste("abcd")]
+    );
+    assert!(parser.pending().is_empty());
+}
+
+#[test]
+fn test_paste_detector() {
+    let key = |code| Event::Key(KeyEvent::new(code));
+    let mut detector = PasteDetector::new();
+    assert_eq!(
+        detector.process([key(KeyCode::Char('a')), key(KeyCode::Enter)]),
+        vec![key(KeyCode::Char('a')), key(KeyCode::Enter)]
+    );
+
+    // Other events split up runs of printable keys
+    let events = [
+        key(KeyCode::Char('a')),
+        key(KeyCode::Char('B')),
+        Event::Key(KeyEvent::new(KeyCode::Char('c')).kind(KeyEventKind::Release)),
+        key(KeyCode::Tab),
+        key(KeyCode::Enter),
+        key(KeyCode::Up),
+        key(KeyCode::Char('x')),
+        Event::Key(KeyEvent::new(KeyCode::Char('y')).modifiers(KeyModifiers::CTRL)),
+        key(KeyCode::Char('z')),
+    ];
+    assert_eq!(
+        detector.process(events.clone()),
+        vec![
+            Event::Paste("aB\t\n".to_string()),
+            key(KeyCode::Up),
+            key(KeyCode::Char('x')),
+            Event::Key(KeyEvent::new(KeyCode::Char('y')).modifiers(KeyModifiers::CTRL)),
+            key(KeyCode::Char('z')),
+        ]
+    );
+    assert!(detector.pending().is_empty());
+
+    // Holding down a key sends repeats, which aren't part of a paste
+    let repeat = Event::Key(KeyEvent::new(KeyCode::Char('a')).kind(KeyEventKind::Repeat));
+    let events = [
+        key(KeyCode::Char('a')),
+        repeat.clone(),
+        repeat.clone(),
+        repeat.clone(),
+        repeat.clone(),
+    ];
+    assert_eq!(detector.process(events.clone()), events);
+
+    // Runs are merged across batches that arrive within the interval
+    let mut detector = PasteDetector::new().max_interval(Duration::from_secs(60));
+    assert_eq!(detector.process([key(KeyCode::Char('a'))]), vec![]);
+    assert!(detector.deadline().is_some());
+    assert_eq!(detector.process([key(KeyCode::Char('b'))]), vec![]);
+    assert_eq!(
+        detector.process([
+            key(KeyCode::Char('c')),
+            key(KeyCode::Char('d')),
+            key(KeyCode::Esc)
+        ]),
+        vec![Event::Paste("abcd".to_string()), key(KeyCode::Esc)]
+    );
+    assert_eq!(detector.deadline(), None);
+    assert_eq!(detector.process([key(KeyCode::Char('a'))]), vec![]);
+    assert_eq!(detector.flush(), vec![key(KeyCode::Char('a'))]);
+
+    let mut detector = PasteDetector::new().min_len(5);
+    assert_eq!(detector.process(events.clone()), events);
+}
+
+#[test]
+fn test_key_notation() {
+    let cases = [
+        (
+            KeyEvent::new(KeyCode::Char('a')).modifiers(KeyModifiers::CTRL | KeyModifiers::SHIFT),
+            "ctrl+shift+a",
+        ),
+        (
+            KeyEvent::new(KeyCode::Enter).modifiers(KeyModifiers::ALT),
+            "alt+enter",
+        ),
+        (KeyEvent::new(KeyCode::F(12)), "f12"),
+        (
+            KeyEvent::new(KeyCode::Left).modifiers(KeyModifiers::SUPER),
+            "super+left",
+        ),
+        (
+            KeyEvent::new(KeyCode::Char('+')).modifiers(KeyModifiers::CTRL),
+            "ctrl+plus",
+        ),
+        (KeyEvent::new(KeyCode::Char(' ')), "space"),
+        (KeyEvent::new(KeyCode::Char('é')), "é"),
+        (
+            KeyEvent::new(KeyCode::Media(MediaKeyCode::PlayPause)),
+            "mediaplaypause",
+        ),
+        (
+            KeyEvent::new(KeyCode::Modifier(
+                ModifierKeyCode::Control,
+                ModifierDirection::Right,
+            ))
+            .kind(KeyEventKind::Release),
+            "release+rightctrl",
+        ),
+        (
+            KeyEvent::new(KeyCode::Modifier(
+                ModifierKeyCode::Shift,
+                ModifierDirection::Unknown,
+            )),
+            "shift",
+        ),
+        (
+            KeyEvent::new(KeyCode::Char('5'))
+                .state(KeyEventState::KEYPAD | KeyEventState::NUM_LOCK)
+                .kind(KeyEventKind::Repeat),
+            "repeat+numlock+keypad+5",
+        ),
+        (
+            KeyEvent::new(KeyCode::Char('a'))
+                .modifiers(KeyModifiers::HYPER | KeyModifiers::META)
+                .state(KeyEventState::CAPS_LOCK),
+            "hyper+meta+capslock+a",
+        ),
+    ];
+    for (key_event, notation) in cases {
+        assert_eq!(key_event.to_string(), notation);
+        assert_eq!(notation.parse::<KeyEvent>().unwrap(), key_event);
+    }
+
+    let ctrl_shift_a =
+        KeyEvent::new(KeyCode::Char('A')).modifiers(KeyModifiers::CTRL | KeyModifiers::SHIFT);
+    for notation in ["ctrl+A", "Shift+Control+a", "ctrl+shift+A"] {
+        let key_event: KeyEvent = notation.parse().unwrap();
+        assert_eq!(key_event, ctrl_shift_a);
+        assert_eq!(key_event.code, KeyCode::Char('A'));
+    }
+    assert_eq!(
+        "ctrl++".parse::<KeyEvent>().unwrap(),
+        "ctrl+plus".parse::<KeyEvent>().unwrap()
+    );
+    assert_eq!(
+        "+".parse::<KeyEvent>().unwrap(),
+        KeyEvent::new(KeyCode::Char('+'))
+    );
+    assert_eq!("Return".parse::<KeyCode>().unwrap(), KeyCode::Enter);
+    assert_eq!(
+        "ctrl+alt".parse::<KeyModifiers>().unwrap(),
+        KeyModifiers::CTRL | KeyModifiers::ALT
+    );
+    assert_eq!(
+        (KeyModifiers::SHIFT | KeyModifiers::CTRL).to_string(),
+        "ctrl+shift"
+    );
+
+    let errors = [
+        (
+            "",
+            NotationError::MissingKey {
+                input: String::new(),
+            },
+        ),
+        (
+            "ctrl+",
+            NotationError::MissingKey {
+                input: "ctrl+".to_string(),
+            },
+        ),
+        (
+            "ctrl+enterr",
+            NotationError::UnknownKey {
+                name: "enterr".to_string(),
+            },
+        ),
+        (
+            "crtl+a",
+            NotationError::UnknownModifier {
+                name: "crtl".to_string(),
+            },
+        ),
+        (
+            "ctrl+Ctrl+a",
+            NotationError::DuplicateModifier {
+                name: "Ctrl".to_string(),
+            },
+        ),
+    ];
+    for (notation, error) in errors {
+        assert_eq!(notation.parse::<KeyEvent>().unwrap_err(), error);
+    }
+}
+
+#[test]
+fn test_vim_notation() {
+    let notation = VimNotation::new();
+    let round_trips = [
+        "<C-a>",
+        "<S-Tab>",
+        "<M-CR>",
+        "<F12>",
+        "<kEnter><k5><kPlus>",
+        "<lt><Bslash><Bar><Space>",
+        "<C-w>j",
+        "<C-S-a>",
+        "<M-A>",
+        "<C->>",
+        "<M-T-D-x>",
+        "A<lt>",
+    ];
+    for vim in round_trips {
+        let keys = notation.parse(vim).unwrap();
+        assert_eq!(notation.format(&keys).unwrap(), vim);
+    }
+
+    let ctrl = |c| KeyEvent::new(KeyCode::Char(c)).modifiers(KeyModifiers::CTRL);
+    let char = |c| KeyEvent::new(KeyCode::Char(c));
+    let cases = [
+        ("<c-A>", vec![ctrl('a')]),
+        (
+            "<C-S-a>",
+            vec![KeyEvent::new(KeyCode::Char('A')).modifiers(KeyModifiers::CTRL)],
+        ),
+        (
+            "<return><ESC>",
+            vec![KeyEvent::new(KeyCode::Enter), KeyEvent::new(KeyCode::Esc)],
+        ),
+        (
+            "<A-x>",
+            vec![KeyEvent::new(KeyCode::Char('x')).modifiers(KeyModifiers::ALT)],
+        ),
+        (
+            "<k5>",
+            vec![KeyEvent::new(KeyCode::Char('5')).state(KeyEventState::KEYPAD)],
+        ),
+        ("<Char-65><Char-0x61>", vec![char('A'), char('a')]),
+        ("<Nop>", vec![]),
+        ("A<", vec![char('A'), char('<')]),
+        ("<NL>", vec![ctrl('j')]),
+        ("<leader>gd", vec![char('\\'), char('g'), char('d')]),
+        (
+            "<foo>",
+            vec![char('<'), char('f'), char('o'), char('o'), char('>')],
+        ),
+        ("<a>", vec![char('<'), char('a'), char('>')]),
+        ("<<>", vec![char('<'), char('<'), char('>')]),
+    ];
+    for (vim, keys) in cases {
+        assert_eq!(notation.parse(vim).unwrap(), keys, "{vim}");
+    }
+
+    let notation = VimNotation::new().leader(char(' ')).local_leader(char(','));
+    assert_eq!(
+        notation.parse("<leader><LocalLeader>").unwrap(),
+        vec![char(' '), char(',')]
+    );
+
+    assert_eq!(
+        notation.parse("").unwrap_err(),
+        NotationError::MissingKey {
+            input: String::new()
+        }
+    );
+    assert_eq!(
+        notation
+            .format(&[KeyEvent::new(KeyCode::Media(MediaKeyCode::Play))])
+            .unwrap_err(),
+        NotationError::UnsupportedKey {
+            key: "mediaplay".to_string()
+        }
+    );
+    assert!(
+        notation
+            .format_key(&KeyEvent::new(KeyCode::Enter).kind(KeyEventKind::Release))
+            .is_err()
+    );
+    for c in ['\x01', '\x1B', '\x7F'] {
+        assert!(
+            notation
+                .format_key(&KeyEvent::new(KeyCode::Char(c)))
+                .is_err()
+        );
+        assert!(
+            notation
+                .format_key(&KeyEvent::new(KeyCode::Char(c)).modifiers(KeyModifiers::ALT))
+                .is_err()
+        );
+    }
+}
+
+#[test]
+fn test_tmux_notation() {
+    let notation = TmuxNotation::new();
+    let round_trips = [
+        "C-M-x", "BSpace", "BTab", "C-BTab", "NPage", "KP5", "KPEnter", "M-Up", "S-F3", "C-S-a",
+        "M-A", "Space", "C-Space", "IC", "DC", "Escape", "^", "-", "M--",
+    ];
+    for tmux in round_trips {
+        let keys = notation.parse(tmux).unwrap();
+        assert_eq!(keys.len(), 1, "{tmux}");
+        assert_eq!(notation.format(&keys).unwrap(), tmux);
+    }
+    let keys = notation.parse("C-b c").unwrap();
+    assert_eq!(notation.format(&keys).unwrap(), "C-b c");
+
+    let ctrl_a = KeyEvent::new(KeyCode::Char('a')).modifiers(KeyModifiers::CTRL);
+    let cases = [
+        ("C-a", ctrl_a),
+        ("^a", ctrl_a),
+        ("C-A", ctrl_a),
+        ("c-a", ctrl_a),
+        (
+            "C-M-x",
+            KeyEvent::new(KeyCode::Char('x')).modifiers(KeyModifiers::CTRL | KeyModifiers::ALT),
+        ),
+        ("bspace", KeyEvent::new(KeyCode::Backspace)),
+        ("PgDn", KeyEvent::new(KeyCode::PageDown)),
+        (
+            "BTab",
+            KeyEvent::new(KeyCode::Tab).modifiers(KeyModifiers::SHIFT),
+        ),
+        (
+            "KP5",
+            KeyEvent::new(KeyCode::Char('5')).state(KeyEventState::KEYPAD),
+        ),
+        (
+            "S-F3",
+            KeyEvent::new(KeyCode::F(3)).modifiers(KeyModifiers::SHIFT),
+        ),
+        ("A", KeyEvent::new(KeyCode::Char('A'))),
+    ];
+    for (tmux, key_event) in cases {
+        assert_eq!(notation.parse(tmux).unwrap(), vec![key_event], "{tmux}");
+    }
+    let char = |c| KeyEvent::new(KeyCode::Char(c));
+    assert_eq!(
+        notation.parse(" C-b  ab ").unwrap(),
+        vec![
+            char('b').modifiers(KeyModifiers::CTRL),
+            char('a'),
+            char('b')
+        ]
+    );
+    assert_eq!(
+        notation.parse("F13").unwrap(),
+        vec![char('F'), char('1'), char('3')]
+    );
+
+    assert_eq!(
+        notation.parse(" ").unwrap_err(),
+        NotationError::MissingKey {
+            input: " ".to_string()
+        }
+    );
+    assert_eq!(
+        notation.parse("C-F13").unwrap_err(),
+        NotationError::UnknownKey {
+            name: "F13".to_string()
+        }
+    );
+    assert_eq!(
+        notation.parse("X-a").unwrap_err(),
+        NotationError::UnknownModifier {
+            name: "X".to_string()
+        }
+    );
+
+    let unsupported = [
+        KeyEvent::new(KeyCode::Char('a')).modifiers(KeyModifiers::SUPER),
+        KeyEvent::new(KeyCode::F(13)),
+        KeyEvent::new(KeyCode::Media(MediaKeyCode::Play)),
+        KeyEvent::new(KeyCode::Enter).kind(KeyEventKind::Release),
+    ];
+    for key_event in unsupported {
+        assert_eq!(
+            notation.format_key(&key_event).unwrap_err(),
+            NotationError::UnsupportedKey {
+                key: key_event.to_string()
+            }
+        );
+    }
+}
+
+#[test]
+fn test_emacs_notation() {
+    let notation = EmacsNotation::new();
+    let round_trips = [
+        "C-x C-f",
+        "M-RET",
+        "C-M-%",
+        "s-<left>",
+        "H-a",
+        "C-S-a",
+        "C-M-S-x",
+        "TAB ESC SPC DEL",
+        "<f12> <kp-5> <kp-enter> <prior> <next>",
+        "<XF86AudioPlay>",
+        "C-x a b",
+    ];
+    for emacs in round_trips {
+        let keys = notation.parse(emacs).unwrap();
+        assert_eq!(notation.format(&keys).unwrap(), emacs);
+    }
+
+    let char = |c| KeyEvent::new(KeyCode::Char(c));
+    let cases = [
+        (
+            "M-<return>",
+            vec![KeyEvent::new(KeyCode::Enter).modifiers(KeyModifiers::ALT)],
+        ),
+        ("A-x", vec![char('x').modifiers(KeyModifiers::ALT)]),
+        (
+            "C-M-%",
+            vec![char('%').modifiers(KeyModifiers::CTRL | KeyModifiers::ALT)],
+        ),
+        (
+            "s-<left>",
+            vec![KeyEvent::new(KeyCode::Left).modifiers(KeyModifiers::SUPER)],
+        ),
+        ("H-a", vec![char('a').modifiers(KeyModifiers::HYPER)]),
+        ("S-a", vec![char('A').modifiers(KeyModifiers::SHIFT)]),
+        ("C-A", vec![char('a').modifiers(KeyModifiers::CTRL)]),
+        (
+            "C-S-a",
+            vec![char('A').modifiers(KeyModifiers::CTRL | KeyModifiers::SHIFT)],
+        ),
+        (
+            "C-x ab",
+            vec![
+                char('x').modifiers(KeyModifiers::CTRL),
+                char('a'),
+                char('b'),
+            ],
+        ),
+        ("<kp-5>", vec![char('5').state(KeyEventState::KEYPAD)]),
+        ("  C--  ", vec![char('-').modifiers(KeyModifiers::CTRL)]),
+    ];
+    for (emacs, keys) in cases {
+        assert_eq!(notation.parse(emacs).unwrap(), keys, "{emacs}");
+    }
+    assert_eq!(
+        notation.format(&notation.parse("C-A").unwrap()).unwrap(),
+        "C-a"
+    );
+    assert_eq!(
+        notation
+            .format(&[char('A').modifiers(KeyModifiers::CTRL)])
+            .unwrap(),
+        "C-S-a"
+    );
+    // Meta would be parsed back as Alt
+    for modifiers in [KeyModifiers::META, KeyModifiers::ALT | KeyModifiers::META] {
+        let key_event = char('x').modifiers(modifiers);
+        assert_eq!(
+            notation.format_key(&key_event).unwrap_err(),
+            NotationError::UnsupportedKey {
+                key: key_event.to_string()
+            }
+        );
+    }
+
+    let notation = EmacsNotation::new().alt_as_meta(false);
+    assert_eq!(
+        notation.parse("M-x A-x").unwrap(),
+        vec![
+            char('x').modifiers(KeyModifiers::META),
+            char('x').modifiers(KeyModifiers::ALT),
+        ]
+    );
+    assert_eq!(
+        notation
+            .format(&[
+                char('x').modifiers(KeyModifiers::ALT | KeyModifiers::META | KeyModifiers::CTRL)
+            ])
+            .unwrap(),
+        "A-C-M-x"
+    );
+
+    assert_eq!(
+        notation.parse(" ").unwrap_err(),
+        NotationError::MissingKey {
+            input: " ".to_string()
+        }
+    );
+    assert_eq!(
+        notation.parse("C-<foo>").unwrap_err(),
+        NotationError::UnknownKey {
+            name: "<foo>".to_string()
+        }
+    );
+    assert_eq!(
+        notation.parse("C-foo").unwrap_err(),
+        NotationError::UnknownKey {
+            name: "foo".to_string()
+        }
+    );
+    let unsupported = [
+        KeyEvent::new(KeyCode::Modifier(
+            ModifierKeyCode::Shift,
+            ModifierDirection::Left,
+        )),
+        KeyEvent::new(KeyCode::Enter).kind(KeyEventKind::Release),
+    ];
+    for key_event in unsupported {
+        assert_eq!(
+            notation.format_key(&key_event).unwrap_err(),
+            NotationError::UnsupportedKey {
+                key: key_event.to_string()
+            }
+        );
+    }
+}
+
+#[test]
+fn test_keymap() {
+    #[derive(Debug, Clone, PartialEq)]
+    enum Action {
+        Down,
+        Top,
+        Quit,
+        SelectAll,
+        Normal,
+    }
+
+    let key = |c| KeyEvent::new(KeyCode::Char(c));
+    let complete = |action| KeymapMatch::Complete {
+        action,
+        count: None,
+    };
+    let base = KeymapLayer::new()
+        .bind([key('g')], Action::Down)
+        .bind([key('g'), key('g')], Action::Top)
+        .bind([key('q')], Action::Quit)
+        .bind([key('0')], Action::Down)
+        .bind(
+            [key('a').modifiers(KeyModifiers::CTRL | KeyModifiers::SHIFT)],
+            Action::SelectAll,
+        );
+    let mut keymap = Keymap::new(base.clone());
+
+    assert_eq!(keymap.process(key('g')), vec![KeymapMatch::Pending]);
+    assert_eq!(keymap.pending(), &[key('g')]);
+    assert_eq!(keymap.process(key('g')), vec![complete(Action::Top)]);
+    assert!(keymap.pending().is_empty());
+
+    // A key that doesn't continue the sequence completes the shorter binding
+    assert_eq!(keymap.process(key('g')), vec![KeymapMatch::Pending]);
+    assert_eq!(
+        keymap.process(key('q')),
+        vec![complete(Action::Down), complete(Action::Quit)]
+    );
+    assert_eq!(keymap.process(key('g')), vec![KeymapMatch::Pending]);
+    assert_eq!(
+        keymap.process(key('x')),
+        vec![
+            complete(Action::Down),
+            KeymapMatch::NoMatch {
+                keys: vec![key('x')]
+            }
+        ]
+    );
+    assert_eq!(keymap.process(key('g')), vec![KeymapMatch::Pending]);
+    assert_eq!(keymap.flush(), vec![complete(Action::Down)]);
+    assert_eq!(
+        keymap.process(key('A').modifiers(KeyModifiers::CTRL)),
+        vec![complete(Action::SelectAll)]
+    );
+
+    // Releases are matched like other keys unless they're ignored
+    let release = key('q').kind(KeyEventKind::Release);
+    assert_eq!(
+        keymap.process(release),
+        vec![KeymapMatch::NoMatch {
+            keys: vec![release]
+        }]
+    );
+    let mut keymap = Keymap::new(base.clone()).ignore_releases(true);
+    assert_eq!(keymap.process(release), vec![]);
+
+    // Timeouts
+    let mut keymap = Keymap::new(base.clone()).timeout(Duration::from_secs(60));
+    assert_eq!(keymap.deadline(), None);
+    keymap.process(key('g'));
+    assert!(keymap.deadline().is_some());
+    let mut keymap = Keymap::new(base.clone()).timeout(Duration::ZERO);
+    keymap.process(key('g'));
+    std::thread::sleep(Duration::from_millis(1));
+    assert_eq!(
+        keymap.process(key('g')),
+        vec![complete(Action::Down), KeymapMatch::Pending]
+    );
+
+    // Counts
+    let mut keymap = Keymap::new(base.clone()).counts(true);
+    assert_eq!(keymap.process(key('1')), vec![KeymapMatch::Pending]);
+    assert_eq!(keymap.process(key('0')), vec![KeymapMatch::Pending]);
+    assert_eq!(keymap.count(), Some(10));
+    keymap.process(key('g'));
+    assert_eq!(
+        keymap.process(key('g')),
+        vec![KeymapMatch::Complete {
+            action: Action::Top,
+            count: Some(10),
+        }]
+    );
+    assert_eq!(keymap.process(key('0')), vec![complete(Action::Down)]);
+    keymap.process(key('3'));
+    assert_eq!(
+        keymap.process(key('x')),
+        vec![KeymapMatch::NoMatch {
+            keys: vec![key('3'), key('x')]
+        }]
+    );
+    keymap.process(key('3'));
+    assert_eq!(
+        keymap.flush(),
+        vec![KeymapMatch::NoMatch {
+            keys: vec![key('3')]
+        }]
+    );
+    assert_eq!(keymap.count(), None);
+
+    // Layers
+    let mut keymap = Keymap::new(base);
+    assert!(keymap.pop_layer().is_none());
+    keymap.push_layer(KeymapLayer::new().bind([KeyEvent::new(KeyCode::Esc)], Action::Normal));
+    assert_eq!(
+        keymap.process(key('q')),
+        vec![KeymapMatch::NoMatch {
+            keys: vec![key('q')]
+        }]
+    );
+    assert_eq!(
+        keymap.process(KeyEvent::new(KeyCode::Esc)),
+        vec![complete(Action::Normal)]
+    );
+    keymap.pop_layer();
+    keymap.push_layer(
+        KeymapLayer::new()
+            .fallthrough(true)
+            .bind([key('q')], Action::Normal),
+    );
+    assert_eq!(keymap.layers().len(), 2);
+    assert_eq!(keymap.process(key('q')), vec![complete(Action::Normal)]);
+    assert_eq!(keymap.process(key('g')), vec![KeymapMatch::Pending]);
+    assert_eq!(keymap.process(key('g')), vec![complete(Action::Top)]);
+
+    let mut layer = KeymapLayer::new();
+    assert_eq!(layer.insert([key('a')], 1), None);
+    assert_eq!(layer.insert([key('a')], 2), Some(1));
+    assert_eq!(layer.insert([] as [KeyEvent; 0], 3), None);
+    assert_eq!(layer.get(&[key('a')]), Some(&2));
+    assert_eq!(layer.get(&[]), None);
+}
+
+#[test]
+fn test_key_pattern() {
+    let ctrl_a = KeyEvent::new(KeyCode::Char('a')).modifiers(KeyModifiers::CTRL);
+    let pattern = KeyPattern::new(ctrl_a);
+    assert!(pattern.matches(&ctrl_a));
+    assert!(pattern.matches(&ctrl_a.state(KeyEventState::CAPS_LOCK | KeyEventState::NUM_LOCK)));
+    assert!(pattern.matches(&ctrl_a.kind(KeyEventKind::Repeat)));
+    assert!(!pattern.matches(&ctrl_a.kind(KeyEventKind::Release)));
+    assert!(!pattern.matches(&KeyEvent::new(KeyCode::Char('a'))));
+    assert!(!pattern.matches(&ctrl_a.modifiers(KeyModifiers::CTRL | KeyModifiers::SHIFT)));
+    assert_eq!("ctrl+a".parse::<KeyPattern>().unwrap(), pattern);
+
+    let strict = pattern.ignore_lock_state(false).match_repeats(false);
+    assert!(!strict.matches(&ctrl_a.state(KeyEventState::CAPS_LOCK)));
+    assert!(!strict.matches(&ctrl_a.kind(KeyEventKind::Repeat)));
+
+    // The pattern's own lock state is ignored too
+    let pattern = KeyPattern::new(ctrl_a.state(KeyEventState::NUM_LOCK));
+    assert!(pattern.matches(&ctrl_a));
+
+    let one = KeyEvent::new(KeyCode::Char('1'));
+    let keypad_one = one.state(KeyEventState::KEYPAD | KeyEventState::NUM_LOCK);
+    assert!(KeyPattern::new(one).matches(&keypad_one));
+    assert!(KeyPattern::new(keypad_one).matches(&one));
+    assert!(
+        !KeyPattern::new(one)
+            .ignore_keypad(false)
+            .matches(&keypad_one)
+    );
+    assert!(
+        KeyPattern::from(KeyCode::Enter)
+            .matches(&KeyEvent::new(KeyCode::Enter).state(KeyEventState::KEYPAD))
+    );
+
+    let left_shift = KeyCode::Modifier(ModifierKeyCode::Shift, ModifierDirection::Left);
+    let right_shift = KeyEvent::new(KeyCode::Modifier(
+        ModifierKeyCode::Shift,
+        ModifierDirection::Right,
+    ));
+    assert!(!KeyPattern::from(left_shift).matches(&right_shift));
+    let pattern = KeyPattern::from(left_shift).ignore_modifier_direction(true);
+    assert!(pattern.matches(&right_shift));
+    assert!(!pattern.matches(&KeyEvent::new(KeyCode::Modifier(
+        ModifierKeyCode::Control,
+        ModifierDirection::Right,
+    ))));
+
+    // Keymaps use patterns for matching
+    let j = KeyEvent::new(KeyCode::Char('j'));
+    let mut keymap = Keymap::new(KeymapLayer::new().bind([j], 1).bind(
+        [KeyPattern::new(j.modifiers(KeyModifiers::CTRL)).match_repeats(false)],
+        2,
+    ));
+    assert_eq!(
+        keymap.process(j.kind(KeyEventKind::Repeat).state(KeyEventState::CAPS_LOCK)),
+        vec![KeymapMatch::Complete {
+            action: 1,
+            count: None
+        }]
+    );
+    assert_eq!(
+        keymap.process(j.modifiers(KeyModifiers::CTRL).kind(KeyEventKind::Repeat)),
+        vec![KeymapMatch::NoMatch {
+            keys: vec![j.modifiers(KeyModifiers::CTRL).kind(KeyEventKind::Repeat)]
+        }]
+    );
+}
+
+#[test]
+fn test_legacy_equivalent() {
+    let key = KeyEvent::new;
+    let ctrl = |c| KeyEvent::new(KeyCode::Char(c)).modifiers(KeyModifiers::CTRL);
+    // Each key is compared with what the legacy parser reports for the bytes that are sent
+    let cases: [(KeyEvent, &[u8]); 20] = [
+        (ctrl('i'), b"\t"),
+        (key(KeyCode::Tab).modifiers(KeyModifiers::CTRL), b"\t"),
+        (ctrl('m'), b"\r"),
+        (key(KeyCode::Enter).modifiers(KeyModifiers::CTRL), b"\r"),
+        (key(KeyCode::Enter).modifiers(KeyModifiers::SHIFT), b"\r"),
+        (ctrl('['), b"\x1B"),
+        (ctrl('3'), b"\x1B"),
+        (ctrl('h'), b"\x08"),
+        (
+            key(KeyCode::Backspace).modifiers(KeyModifiers::CTRL),
+            b"\x08",
+        ),
+        (ctrl('?'), b"\x7F"),
+        (ctrl('A'), b"\x01"),
+        (ctrl('j'), b"\n"),
+        (ctrl('@'), b"\0"),
+        (ctrl('2'), b"\0"),
+        (ctrl('\\'), b"\x1C"),
+        (ctrl(']'), b"\x1D"),
+        (ctrl('/'), b"\x1F"),
+        (
+            key(KeyCode::Char('i')).modifiers(KeyModifiers::CTRL | KeyModifiers::ALT),
+            b"\x1B\t",
+        ),
+        (
+            key(KeyCode::Char('1'))
+                .modifiers(KeyModifiers::SHIFT)
+                .shifted_key('!'),
+            b"!",
+        ),
+        (
+            key(KeyCode::Char('5'))
+                .state(KeyEventState::KEYPAD | KeyEventState::NUM_LOCK)
+                .kind(KeyEventKind::Repeat),
+            b"5",
+        ),
+    ];
+    for (key_event, bytes) in cases {
+        let Some(Event::Key(parsed)) = parse_event(bytes).unwrap() else {
+            panic!("{bytes:?} isn't a key event");
+        };
+        assert_eq!(key_event.legacy_equivalent(), Some(parsed), "{key_event}");
+        assert_eq!(
+            Event::Key(key_event).legacy_equivalent(),
+            Some(Event::Key(parsed))
+        );
+    }
+
+    assert_eq!(
+        key(KeyCode::Char('a'))
+            .kind(KeyEventKind::Release)
+            .legacy_equivalent(),
+        None
+    );
+    assert_eq!(
+        key(KeyCode::Media(MediaKeyCode::Play)).legacy_equivalent(),
+        None
+    );
+    assert_eq!(key(KeyCode::CapsLock).legacy_equivalent(), None);
+    // Keys that legacy terminals can't send don't have an equivalent
+    let unsendable = [
+        key(KeyCode::Char('q')).modifiers(KeyModifiers::SUPER),
+        key(KeyCode::Up).modifiers(KeyModifiers::CTRL | KeyModifiers::SUPER),
+        key(KeyCode::Char('x')).modifiers(KeyModifiers::HYPER | KeyModifiers::META),
+        ctrl('1'),
+        ctrl('é'),
+        key(KeyCode::Char('1')).modifiers(KeyModifiers::SHIFT),
+        key(KeyCode::Char('1'))
+            .modifiers(KeyModifiers::CTRL | KeyModifiers::SHIFT)
+            .shifted_key('!'),
+    ];
+    for key_event in unsendable {
+        assert_eq!(key_event.legacy_equivalent(), None, "{key_event}");
+    }
+    assert_eq!(
+        "super+q".parse::<KeyEvent>().unwrap().legacy_equivalent(),
+        None
+    );
+    assert_eq!(
+        Event::FocusGained.legacy_equivalent(),
+        Some(Event::FocusGained)
+    );
+
+    assert!(ctrl('i').legacy_conflicts_with(&key(KeyCode::Tab)));
+    assert!(
+        ctrl('h').legacy_conflicts_with(&key(KeyCode::Backspace).modifiers(KeyModifiers::CTRL))
+    );
+    assert!(!ctrl('i').legacy_conflicts_with(&ctrl('i')));
+    assert!(!ctrl('i').legacy_conflicts_with(&ctrl('j')));
+    assert!(
+        !key(KeyCode::Media(MediaKeyCode::Play))
+            .legacy_conflicts_with(&key(KeyCode::Media(MediaKeyCode::Pause)))
+    );
+
+    let pattern = KeyPattern::new(ctrl('i'));
+    assert!(!pattern.matches(&key(KeyCode::Tab)));
+    let pattern = pattern.match_legacy_equivalents(true);
+    assert!(pattern.matches(&key(KeyCode::Tab)));
+    assert!(!pattern.matches(&key(KeyCode::Tab).kind(KeyEventKind::Release)));
+    assert!(
+        KeyPattern::new(key(KeyCode::Media(MediaKeyCode::Play)))
+            .match_legacy_equivalents(true)
+            .matches(&key(KeyCode::Media(MediaKeyCode::Play)))
+    );
+
+    // Modifiers that legacy terminals can't report aren't dropped
+    let cases = [
+        ("super+q", key(KeyCode::Char('q'))),
+        ("ctrl+1", key(KeyCode::Char('1'))),
+        ("shift+1", key(KeyCode::Char('1'))),
+    ];
+    for (notation, key_event) in cases {
+        let pattern: KeyPattern = notation.parse().unwrap();
+        let pattern = pattern.match_legacy_equivalents(true);
+        assert!(!pattern.matches(&key_event), "{notation}");
+        assert!(pattern.matches(pattern.key_event()), "{notation}");
+        assert!(
+            !KeyPattern::new(key_event)
+                .match_legacy_equivalents(true)
+                .matches(pattern.key_event()),
+            "{notation}"
+        );
+    }
+}